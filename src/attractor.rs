@@ -0,0 +1,856 @@
+use nannou::prelude::*;
+use crate::effect::Easing;
+
+// A 2D map iterated over and over, p(n+1) = step(p(n)).
+// Every attractor is a handful of numbers, the presets are sets of them worth looking at.
+pub trait Attractor {
+    fn name(&self) -> &'static str;
+    fn step(&self, p: Vec2) -> Vec2;
+    fn param_names(&self) -> &'static [&'static str];
+    fn params(&self) -> Vec<f32>;
+    // missing values keep what they were, extra ones are ignored
+    fn set_params(&mut self, params: &[f32]);
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])];
+
+    // some of them escape from the origin, this is where to start instead
+    fn start(&self) -> Vec2 {
+        vec2(0.1, 0.1)
+    }
+
+    fn use_preset(&mut self, name: &str) -> bool {
+        match self.presets().iter().find(|(n, _)| *n == name) {
+            Some((_, params)) => {
+                self.set_params(params);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The box the orbit from start stays in, after skipping the first few points
+    fn bounds(&self, iterations: usize) -> Rect {
+        let mut p = self.start();
+        for _ in 0..100 {
+            p = self.step(p);
+        }
+        let (mut min, mut max) = (p, p);
+        for _ in 0..iterations {
+            p = self.step(p);
+            if !p.is_finite() {
+                break;
+            }
+            min = min.min(p);
+            max = max.max(p);
+        }
+        Rect::from_corners(min, max)
+    }
+}
+
+fn copy_params(to: &mut [f32], from: &[f32]) {
+    for (t, f) in to.iter_mut().zip(from.iter()) {
+        *t = *f;
+    }
+}
+
+// x = sin(a y) + c cos(a x), y = sin(b x) + d cos(b y)
+pub struct Clifford {
+    pub params: [f32; 4],
+}
+
+impl Clifford {
+    pub fn new(a: f32, b: f32, c: f32, d: f32) -> Self {
+        Self { params: [a, b, c, d] }
+    }
+}
+
+impl Default for Clifford {
+    fn default() -> Self {
+        Clifford::new(-1.4, 1.6, 1.0, 0.7)
+    }
+}
+
+impl Attractor for Clifford {
+    fn name(&self) -> &'static str {
+        "clifford"
+    }
+
+    fn step(&self, p: Vec2) -> Vec2 {
+        let [a, b, c, d] = self.params;
+        vec2(
+            (a * p.y).sin() + c * (a * p.x).cos(),
+            (b * p.x).sin() + d * (b * p.y).cos(),
+        )
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["a", "b", "c", "d"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[-1.4, 1.6, 1.0, 0.7]),
+            ("sketch", &[1.5, -1.8, 1.6, 0.9]),
+            ("wings", &[1.7, 1.7, 0.6, 1.2]),
+            ("swirl", &[-1.7, 1.3, -0.1, -1.2]),
+        ]
+    }
+}
+
+// x = sin(a y) - cos(b x), y = sin(c x) - cos(d y)
+pub struct DeJong {
+    pub params: [f32; 4],
+}
+
+impl DeJong {
+    pub fn new(a: f32, b: f32, c: f32, d: f32) -> Self {
+        Self { params: [a, b, c, d] }
+    }
+}
+
+impl Default for DeJong {
+    fn default() -> Self {
+        DeJong::new(1.4, -2.3, 2.4, -2.1)
+    }
+}
+
+impl Attractor for DeJong {
+    fn name(&self) -> &'static str {
+        "de jong"
+    }
+
+    fn step(&self, p: Vec2) -> Vec2 {
+        let [a, b, c, d] = self.params;
+        vec2(
+            (a * p.y).sin() - (b * p.x).cos(),
+            (c * p.x).sin() - (d * p.y).cos(),
+        )
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["a", "b", "c", "d"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[1.4, -2.3, 2.4, -2.1]),
+            ("feathers", &[2.01, -2.53, 1.61, -0.33]),
+            ("spiral", &[-2.7, -0.09, -0.86, -2.2]),
+            ("cloud", &[-0.827, -1.637, 1.659, -0.943]),
+        ]
+    }
+}
+
+// x = d sin(a x) - sin(b y), y = c cos(a x) + cos(b y)
+pub struct Svensson {
+    pub params: [f32; 4],
+}
+
+impl Svensson {
+    pub fn new(a: f32, b: f32, c: f32, d: f32) -> Self {
+        Self { params: [a, b, c, d] }
+    }
+}
+
+impl Default for Svensson {
+    fn default() -> Self {
+        Svensson::new(1.5, -1.8, 1.6, 0.9)
+    }
+}
+
+impl Attractor for Svensson {
+    fn name(&self) -> &'static str {
+        "svensson"
+    }
+
+    fn step(&self, p: Vec2) -> Vec2 {
+        let [a, b, c, d] = self.params;
+        vec2(
+            d * (a * p.x).sin() - (b * p.y).sin(),
+            c * (a * p.x).cos() + (b * p.y).cos(),
+        )
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["a", "b", "c", "d"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[1.5, -1.8, 1.6, 0.9]),
+            ("ribbons", &[-1.78, 1.29, -0.09, -1.18]),
+            ("lace", &[1.4, 1.56, 1.4, -6.56]),
+        ]
+    }
+}
+
+// x = sin(x y / b) y + cos(a x - y), y = x + sin(y) / b
+pub struct Bedhead {
+    pub params: [f32; 2],
+}
+
+impl Bedhead {
+    pub fn new(a: f32, b: f32) -> Self {
+        Self { params: [a, b] }
+    }
+}
+
+impl Default for Bedhead {
+    fn default() -> Self {
+        Bedhead::new(-0.81, -0.92)
+    }
+}
+
+impl Attractor for Bedhead {
+    fn name(&self) -> &'static str {
+        "bedhead"
+    }
+
+    fn step(&self, p: Vec2) -> Vec2 {
+        let [a, b] = self.params;
+        vec2(
+            (p.x * p.y / b).sin() * p.y + (a * p.x - p.y).cos(),
+            p.x + p.y.sin() / b,
+        )
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["a", "b"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[-0.81, -0.92]),
+            ("loops", &[-0.64, 0.76]),
+            ("knot", &[0.06, 0.98]),
+            ("scribble", &[-0.67, 0.83]),
+        ]
+    }
+}
+
+// Martin's map, x = y - sign(x) sqrt(|b x - c|), y = a - x
+pub struct Hopalong {
+    pub params: [f32; 3],
+}
+
+impl Hopalong {
+    pub fn new(a: f32, b: f32, c: f32) -> Self {
+        Self { params: [a, b, c] }
+    }
+}
+
+impl Default for Hopalong {
+    fn default() -> Self {
+        Hopalong::new(0.4, 1., 0.)
+    }
+}
+
+impl Attractor for Hopalong {
+    fn name(&self) -> &'static str {
+        "hopalong"
+    }
+
+    fn step(&self, p: Vec2) -> Vec2 {
+        let [a, b, c] = self.params;
+        vec2(
+            p.y - p.x.signum() * (b * p.x - c).abs().sqrt(),
+            a - p.x,
+        )
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["a", "b", "c"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[0.4, 1., 0.]),
+            ("mosaic", &[-11., 0.05, 0.5]),
+            ("rings", &[1.1, 0.5, 1.]),
+        ]
+    }
+}
+
+// x = x² - y² + a x + b y, y = 2 x y + c x + d y
+pub struct Tinkerbell {
+    pub params: [f32; 4],
+}
+
+impl Tinkerbell {
+    pub fn new(a: f32, b: f32, c: f32, d: f32) -> Self {
+        Self { params: [a, b, c, d] }
+    }
+}
+
+impl Default for Tinkerbell {
+    fn default() -> Self {
+        Tinkerbell::new(0.9, -0.6013, 2.0, 0.5)
+    }
+}
+
+impl Attractor for Tinkerbell {
+    fn name(&self) -> &'static str {
+        "tinkerbell"
+    }
+
+    fn step(&self, p: Vec2) -> Vec2 {
+        let [a, b, c, d] = self.params;
+        vec2(
+            p.x * p.x - p.y * p.y + a * p.x + b * p.y,
+            2. * p.x * p.y + c * p.x + d * p.y,
+        )
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["a", "b", "c", "d"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[0.9, -0.6013, 2.0, 0.5]),
+            ("tight", &[0.9, -0.6013, 2.0, 0.45]),
+        ]
+    }
+
+    fn start(&self) -> Vec2 {
+        vec2(-0.72, -0.64)
+    }
+}
+
+// x = y + a (1 - b y²) y + f(x), y = -x + f(new x)
+// with f(x) = mu x + 2 (1 - mu) x² / (1 + x²)
+pub struct GumowskiMira {
+    pub params: [f32; 3],
+}
+
+impl GumowskiMira {
+    pub fn new(a: f32, b: f32, mu: f32) -> Self {
+        Self { params: [a, b, mu] }
+    }
+}
+
+impl Default for GumowskiMira {
+    fn default() -> Self {
+        GumowskiMira::new(0.008, 0.05, -0.496)
+    }
+}
+
+impl Attractor for GumowskiMira {
+    fn name(&self) -> &'static str {
+        "gumowski-mira"
+    }
+
+    fn step(&self, p: Vec2) -> Vec2 {
+        let [a, b, mu] = self.params;
+        let f = |x: f32| mu * x + 2. * (1. - mu) * x * x / (1. + x * x);
+        let x = p.y + a * (1. - b * p.y * p.y) * p.y + f(p.x);
+        vec2(x, -p.x + f(x))
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["a", "b", "mu"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[0.008, 0.05, -0.496]),
+            ("star", &[0.009, 0.05, -0.801]),
+            ("butterfly", &[0.008, 0.05, -0.7]),
+        ]
+    }
+
+    fn start(&self) -> Vec2 {
+        vec2(0.5, 0.5)
+    }
+}
+
+// the laser in a ring cavity, u close to 1 gets chaotic
+// t = 0.4 - 6 / (1 + x² + y²), x = 1 + u (x cos t - y sin t), y = u (x sin t + y cos t)
+pub struct Ikeda {
+    pub params: [f32; 1],
+}
+
+impl Ikeda {
+    pub fn new(u: f32) -> Self {
+        Self { params: [u] }
+    }
+}
+
+impl Default for Ikeda {
+    fn default() -> Self {
+        Ikeda::new(0.918)
+    }
+}
+
+impl Attractor for Ikeda {
+    fn name(&self) -> &'static str {
+        "ikeda"
+    }
+
+    fn step(&self, p: Vec2) -> Vec2 {
+        let [u] = self.params;
+        let t = 0.4 - 6. / (1. + p.length_squared());
+        let (s, c) = t.sin_cos();
+        vec2(1. + u * (p.x * c - p.y * s), u * (p.x * s + p.y * c))
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["u"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[0.918]),
+            ("tight", &[0.85]),
+            ("wild", &[0.99]),
+        ]
+    }
+}
+
+pub const ATTRACTOR_NAMES: [&str; 8] = [
+    "clifford", "de jong", "svensson", "bedhead", "hopalong", "tinkerbell", "gumowski-mira", "ikeda",
+];
+
+// with its default parameters
+pub fn by_name(name: &str) -> Option<Box<dyn Attractor + Send + Sync>> {
+    let attractor: Box<dyn Attractor + Send + Sync> = match name {
+        "clifford" => Box::new(Clifford::default()),
+        "de jong" => Box::new(DeJong::default()),
+        "svensson" => Box::new(Svensson::default()),
+        "bedhead" => Box::new(Bedhead::default()),
+        "hopalong" => Box::new(Hopalong::default()),
+        "tinkerbell" => Box::new(Tinkerbell::default()),
+        "gumowski-mira" => Box::new(GumowskiMira::default()),
+        "ikeda" => Box::new(Ikeda::default()),
+        _ => return None,
+    };
+    Some(attractor)
+}
+
+// Moves a parameter set to another one over duration seconds, starting from wherever it is now
+#[derive(Debug, Clone)]
+pub struct ParamMorph {
+    from: Vec<f32>,
+    to: Vec<f32>,
+    progress: f32,
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+impl ParamMorph {
+    pub fn new(params: &[f32], duration: f32) -> Self {
+        Self {
+            from: params.to_vec(),
+            to: params.to_vec(),
+            progress: 1.,
+            duration,
+            easing: Easing::SineInOut,
+        }
+    }
+
+    pub fn morph_to(&mut self, target: &[f32]) {
+        self.from = self.current();
+        self.to = target.to_vec();
+        self.progress = 0.;
+    }
+
+    // changes where a running morph is going, carrying on from where it is, straight there when it's done
+    pub fn retarget(&mut self, target: &[f32]) {
+        if self.is_done() {
+            self.jump_to(target);
+        } else {
+            self.morph_to(target);
+        }
+    }
+
+    // no animation, straight there
+    pub fn jump_to(&mut self, target: &[f32]) {
+        self.from = target.to_vec();
+        self.to = target.to_vec();
+        self.progress = 1.;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.duration <= 0. {
+            self.progress = 1.;
+        } else {
+            self.progress = (self.progress + dt / self.duration).min(1.);
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.progress >= 1.
+    }
+
+    pub fn target(&self) -> &[f32] {
+        &self.to
+    }
+
+    pub fn current(&self) -> Vec<f32> {
+        let t = self.easing.apply(self.progress);
+        self.from.iter().zip(self.to.iter()).map(|(a, b)| a + (b - a) * t).collect()
+    }
+}
+
+// A flow in 3D, the orbit follows derivative() over time instead of jumping from point to point.
+// They need an integrator to move, see ode.rs
+pub trait ContinuousAttractor {
+    fn name(&self) -> &'static str;
+    fn derivative(&self, p: Vec3) -> Vec3;
+    fn param_names(&self) -> &'static [&'static str];
+    fn params(&self) -> Vec<f32>;
+    fn set_params(&mut self, params: &[f32]);
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])];
+
+    fn start(&self) -> Vec3 {
+        vec3(0.1, 0., 0.)
+    }
+
+    // some move a lot slower than others, this many time units look about the same
+    fn time_scale(&self) -> f32 {
+        1.
+    }
+
+    fn use_preset(&mut self, name: &str) -> bool {
+        match self.presets().iter().find(|(n, _)| *n == name) {
+            Some((_, params)) => {
+                self.set_params(params);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// x = s (y - x), y = x (r - z) - y, z = x y - b z
+pub struct Lorenz {
+    pub params: [f32; 3],
+}
+
+impl Default for Lorenz {
+    fn default() -> Self {
+        Self { params: [10., 28., 8. / 3.] }
+    }
+}
+
+impl ContinuousAttractor for Lorenz {
+    fn name(&self) -> &'static str {
+        "lorenz"
+    }
+
+    fn derivative(&self, p: Vec3) -> Vec3 {
+        let [s, r, b] = self.params;
+        vec3(s * (p.y - p.x), p.x * (r - p.z) - p.y, p.x * p.y - b * p.z)
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["sigma", "rho", "beta"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[10., 28., 2.6667]),
+            ("wide", &[10., 45., 2.6667]),
+            ("periodic", &[10., 99.96, 2.6667]),
+        ]
+    }
+}
+
+// x = -y - z, y = x + a y, z = b + z (x - c)
+pub struct Rossler {
+    pub params: [f32; 3],
+}
+
+impl Default for Rossler {
+    fn default() -> Self {
+        Self { params: [0.2, 0.2, 5.7] }
+    }
+}
+
+impl ContinuousAttractor for Rossler {
+    fn name(&self) -> &'static str {
+        "rossler"
+    }
+
+    fn derivative(&self, p: Vec3) -> Vec3 {
+        let [a, b, c] = self.params;
+        vec3(-p.y - p.z, p.x + a * p.y, b + p.z * (p.x - c))
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["a", "b", "c"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[0.2, 0.2, 5.7]),
+            ("funnel", &[0.3, 0.2, 5.7]),
+            ("spiral", &[0.1, 0.1, 14.]),
+        ]
+    }
+
+    fn time_scale(&self) -> f32 {
+        3.
+    }
+}
+
+// x = (z - b) x - d y, y = d x + (z - b) y,
+// z = c + a z - z³ / 3 - (x² + y²)(1 + e z) + f z x³
+pub struct Aizawa {
+    pub params: [f32; 6],
+}
+
+impl Default for Aizawa {
+    fn default() -> Self {
+        Self { params: [0.95, 0.7, 0.6, 3.5, 0.25, 0.1] }
+    }
+}
+
+impl ContinuousAttractor for Aizawa {
+    fn name(&self) -> &'static str {
+        "aizawa"
+    }
+
+    fn derivative(&self, p: Vec3) -> Vec3 {
+        let [a, b, c, d, e, f] = self.params;
+        vec3(
+            (p.z - b) * p.x - d * p.y,
+            d * p.x + (p.z - b) * p.y,
+            c + a * p.z - p.z.powi(3) / 3. - (p.x * p.x + p.y * p.y) * (1. + e * p.z) + f * p.z * p.x.powi(3),
+        )
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["a", "b", "c", "d", "e", "f"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[0.95, 0.7, 0.6, 3.5, 0.25, 0.1]),
+        ]
+    }
+}
+
+// Thomas' cyclically symmetric attractor, x = sin y - b x, and the same for y and z
+pub struct Thomas {
+    pub params: [f32; 1],
+}
+
+impl Default for Thomas {
+    fn default() -> Self {
+        Self { params: [0.208186] }
+    }
+}
+
+impl ContinuousAttractor for Thomas {
+    fn name(&self) -> &'static str {
+        "thomas"
+    }
+
+    fn derivative(&self, p: Vec3) -> Vec3 {
+        let [b] = self.params;
+        vec3(p.y.sin() - b * p.x, p.z.sin() - b * p.y, p.x.sin() - b * p.z)
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        &["b"]
+    }
+
+    fn params(&self) -> Vec<f32> {
+        self.params.to_vec()
+    }
+
+    fn set_params(&mut self, params: &[f32]) {
+        copy_params(&mut self.params, params);
+    }
+
+    fn presets(&self) -> &'static [(&'static str, &'static [f32])] {
+        &[
+            ("classic", &[0.208186]),
+            ("labyrinth", &[0.1]),
+        ]
+    }
+
+    fn start(&self) -> Vec3 {
+        vec3(0.1, 0., -0.1)
+    }
+
+    fn time_scale(&self) -> f32 {
+        8.
+    }
+}
+
+pub const CONTINUOUS_ATTRACTOR_NAMES: [&str; 4] = ["lorenz", "rossler", "aizawa", "thomas"];
+
+pub fn continuous_by_name(name: &str) -> Option<Box<dyn ContinuousAttractor + Send + Sync>> {
+    let attractor: Box<dyn ContinuousAttractor + Send + Sync> = match name {
+        "lorenz" => Box::new(Lorenz::default()),
+        "rossler" => Box::new(Rossler::default()),
+        "aizawa" => Box::new(Aizawa::default()),
+        "thomas" => Box::new(Thomas::default()),
+        _ => return None,
+    };
+    Some(attractor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_preset_stays_bounded() {
+        for name in ATTRACTOR_NAMES.iter() {
+            let mut attractor = by_name(name).unwrap();
+            assert_eq!(attractor.name(), *name);
+            let presets = attractor.presets();
+            for (preset, params) in presets.iter() {
+                assert!(attractor.use_preset(preset));
+                assert_eq!(attractor.params(), params.to_vec());
+                assert_eq!(attractor.param_names().len(), params.len());
+                let b = attractor.bounds(10000);
+                assert!(b.w().is_finite() && b.h().is_finite(), "{} {}", name, preset);
+                assert!(b.w() < 1000. && b.h() < 1000., "{} {} escaped", name, preset);
+            }
+        }
+    }
+
+    #[test]
+    fn clifford_matches_the_formula() {
+        let c = Clifford::new(1.5, -1.8, 1.6, 0.9);
+        let p = vec2(0.3, -0.2);
+        let expected = vec2(
+            (1.5f32 * -0.2).sin() + 1.6 * (1.5f32 * 0.3).cos(),
+            (-1.8f32 * 0.3).sin() + 0.9 * (-1.8f32 * -0.2).cos(),
+        );
+        assert!(c.step(p).distance(expected) < 1e-6);
+        assert!(by_name("nope").is_none());
+    }
+
+    #[test]
+    fn morph_between_presets() {
+        let mut morph = ParamMorph::new(&[0., 1.], 2.);
+        assert!(morph.is_done());
+        morph.morph_to(&[2., -1.]);
+        assert_eq!(morph.current(), vec![0., 1.]);
+        morph.update(1.);
+        assert_eq!(morph.current(), vec![1., 0.]);
+        // changing course half way starts from where it is
+        morph.morph_to(&[1., 1.]);
+        assert_eq!(morph.current(), vec![1., 0.]);
+        morph.update(5.);
+        assert!(morph.is_done());
+        assert_eq!(morph.current(), vec![1., 1.]);
+        // a nudge on the way keeps it going from where it is
+        morph.morph_to(&[3., 1.]);
+        morph.update(1.);
+        assert_eq!(morph.current(), vec![2., 1.]);
+        morph.retarget(&[5., 1.]);
+        assert!(!morph.is_done());
+        assert_eq!(morph.current(), vec![2., 1.]);
+        morph.update(1.);
+        assert_eq!(morph.current(), vec![3.5, 1.]);
+        morph.update(1.);
+        assert_eq!(morph.current(), vec![5., 1.]);
+        morph.retarget(&[5., 2.]);
+        assert_eq!(morph.current(), vec![5., 2.]);
+    }
+
+    #[test]
+    fn continuous_attractors_stay_bounded() {
+        use crate::ode::Integrator;
+        for name in CONTINUOUS_ATTRACTOR_NAMES.iter() {
+            let attractor = continuous_by_name(name).unwrap();
+            let f = |p: Vec3| attractor.derivative(p);
+            let mut integrator = Integrator::rk4(0.005);
+            let p = integrator.advance(&f, attractor.start(), 20. * attractor.time_scale());
+            assert!(p.is_finite() && p.length() < 100., "{} went to {:?}", name, p);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use nannou::prelude::*;
+use crate::attractor::{by_name, Attractor};
+
+// Where the presets found by the search are kept, the attractor sketch loads it too.
+// Relative to the working directory, both bins take another path as an argument.
+pub const PRESETS_FILE: &str = "attractor_presets.txt";
+
+#[derive(Debug, Clone)]
+pub struct SearchParams {
+    // orbit length used to judge a candidate
+    pub iterations: usize,
+    // above zero nearby orbits fly apart, which is what chaos means
+    pub min_lyapunov: f32,
+    // fraction of the bounding box the orbit visits, low is a few points or a thin loop,
+    // high is a shapeless blob
+    pub min_area: f32,
+    pub max_area: f32,
+    // anything further out than this escaped
+    pub max_extent: f32,
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        Self {
+            iterations: 20000,
+            min_lyapunov: 0.05,
+            min_area: 0.05,
+            max_area: 0.6,
+            max_extent: 100.,
+        }
+    }
+}
+
+// Largest Lyapunov exponent, from how fast a second orbit a tiny bit away drifts off.
+// The second orbit is pulled back to the same distance after every step.
+pub fn lyapunov<A: Attractor + ?Sized>(attractor: &A, iterations: usize) -> f32 {
+    let d0 = 1e-4;
+    let mut p = attractor.start();
+    for _ in 0..100 {
+        p = attractor.step(p);
+    }
+    let mut q = p + vec2(d0, 0.);
+    let mut sum = 0.;
+    for _ in 0..iterations {
+        p = attractor.step(p);
+        q = attractor.step(q);
+        if !p.is_finite() || !q.is_finite() {
+            return f32::NAN;
+        }
+        let d = p.distance(q);
+        if d == 0. {
+            // fell into a fixed point together
+            return f32::NEG_INFINITY;
+        }
+        sum += (d / d0).ln();
+        q = p + (q - p) * (d0 / d);
+    }
+    sum / iterations as f32
+}
+
+// Fraction of the cells of a grid x grid box around the orbit it passes through
+pub fn area_ratio<A: Attractor + ?Sized>(attractor: &A, iterations: usize, grid: usize) -> f32 {
+    let bounds = attractor.bounds(iterations);
+    if bounds.w() == 0. || bounds.h() == 0. {
+        return 0.;
+    }
+    let mut visited = vec![false; grid * grid];
+    let mut p = attractor.start();
+    for _ in 0..100 {
+        p = attractor.step(p);
+    }
+    for _ in 0..iterations {
+        p = attractor.step(p);
+        let x = ((p.x - bounds.left()) / bounds.w() * grid as f32) as usize;
+        let y = ((p.y - bounds.bottom()) / bounds.h() * grid as f32) as usize;
+        visited[y.min(grid - 1) * grid + x.min(grid - 1)] = true;
+    }
+    visited.iter().filter(|v| **v).count() as f32 / visited.len() as f32
+}
+
+pub fn is_interesting<A: Attractor + ?Sized>(attractor: &A, params: &SearchParams) -> bool {
+    let bounds = attractor.bounds(params.iterations);
+    let extent = bounds.w().max(bounds.h());
+    if !extent.is_finite() || extent > params.max_extent || extent < 1e-3 {
+        return false;
+    }
+    let l = lyapunov(attractor, params.iterations);
+    if l.is_nan() || l <= params.min_lyapunov {
+        return false;
+    }
+    let area = area_ratio(attractor, params.iterations, 64);
+    area >= params.min_area && area <= params.max_area
+}
+
+// Tries up to tries random parameter sets around the attractor's defaults, returns the good ones
+pub fn search(name: &str, count: usize, tries: usize, params: &SearchParams) -> Vec<Vec<f32>> {
+    let mut attractor = match by_name(name) {
+        Some(a) => a,
+        None => return vec![],
+    };
+    let defaults = attractor.params();
+    let mut found = vec![];
+    for _ in 0..tries {
+        let candidate: Vec<f32> = defaults.iter()
+            .map(|v| v + random_range(-1., 1.) * v.abs().max(0.5) * 1.5)
+            .collect();
+        attractor.set_params(&candidate);
+        if is_interesting(attractor.as_ref(), params) {
+            found.push(candidate);
+            if found.len() >= count {
+                break;
+            }
+        }
+    }
+    found
+}
+
+// One preset per line, the attractor name, a colon and the parameters.
+// Empty lines and lines starting with # are skipped.
+pub fn parse_presets(text: &str) -> Vec<(String, Vec<f32>)> {
+    text.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| {
+            let (name, values) = l.split_once(':')?;
+            let values: Result<Vec<f32>, _> = values.split_whitespace().map(|v| v.parse()).collect();
+            Some((name.trim().to_string(), values.ok()?))
+        })
+        .collect()
+}
+
+pub fn format_presets(presets: &[(String, Vec<f32>)]) -> String {
+    presets.iter()
+        .map(|(name, values)| {
+            let values: Vec<String> = values.iter().map(|v| format!("{:.4}", v)).collect();
+            format!("{}: {}\n", name, values.join(" "))
+        })
+        .collect()
+}
+
+pub fn load_presets<P: AsRef<Path>>(path: P) -> io::Result<Vec<(String, Vec<f32>)>> {
+    Ok(parse_presets(&fs::read_to_string(path)?))
+}
+
+// adds to whatever is already in the file, nothing to add leaves it alone
+pub fn save_presets<P: AsRef<Path>>(path: P, presets: &[(String, Vec<f32>)]) -> io::Result<()> {
+    if presets.is_empty() {
+        return Ok(());
+    }
+    let mut text = fs::read_to_string(&path).unwrap_or_default();
+    text.push_str(&format_presets(presets));
+    fs::write(path, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attractor::{Clifford, Ikeda};
+
+    #[test]
+    fn chaos_and_order() {
+        let clifford = Clifford::default();
+        assert!(lyapunov(&clifford, 10000) > 0.1);
+        assert!(is_interesting(&clifford, &SearchParams::default()));
+        // settles down on a single point
+        let ikeda = Ikeda::new(0.3);
+        assert!(lyapunov(&ikeda, 10000) < 0.);
+        assert!(area_ratio(&ikeda, 10000, 64) < 0.01);
+        assert!(!is_interesting(&ikeda, &SearchParams::default()));
+    }
+
+    #[test]
+    fn presets_round_trip() {
+        let presets = vec![
+            ("clifford".to_string(), vec![-1.4, 1.6, 1., 0.7]),
+            ("de jong".to_string(), vec![1.4, -2.3, 2.4, -2.1]),
+        ];
+        let text = format!("# found by the search\n\n{}broken: 1 x\n", format_presets(&presets));
+        assert_eq!(parse_presets(&text), presets);
+
+        let path = std::env::temp_dir().join(format!("attractor_presets_{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        // nothing found, no file
+        save_presets(&path, &[]).unwrap();
+        assert!(!path.exists());
+        save_presets(&path, &presets[..1]).unwrap();
+        save_presets(&path, &presets[1..]).unwrap();
+        assert_eq!(load_presets(&path).unwrap(), presets);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+// Renders an attractor as a density image without opening a window
+//
+//     cargo run --release --bin attractor_render -- [attractor] [preset] [size] [iterations] [output.png]
+//
+// e.g. cargo run --release --bin attractor_render -- "de jong" feathers 3000 100000000 feathers.png
+
+use std::{env, process};
+use nannou_utils::attractor::{by_name, ATTRACTOR_NAMES};
+use nannou_utils::density::{save_png, Gradient, Histogram, ToneMap};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let arg = |ii: usize, default: &str| args.get(ii).cloned().unwrap_or_else(|| default.to_string());
+
+    let name = arg(0, "clifford");
+    let mut attractor = match by_name(&name) {
+        Some(a) => a,
+        None => {
+            eprintln!("unknown attractor {}, pick one of {:?}", name, ATTRACTOR_NAMES);
+            process::exit(1);
+        }
+    };
+    let preset = arg(1, "classic");
+    if !attractor.use_preset(&preset) {
+        let names: Vec<&str> = attractor.presets().iter().map(|(n, _)| *n).collect();
+        eprintln!("unknown preset {}, {} has {:?}", preset, name, names);
+        process::exit(1);
+    }
+    let size: usize = arg(2, "2000").parse().expect("size should be a number");
+    let iterations: usize = arg(3, "20000000").parse().expect("iterations should be a number");
+    let output = arg(4, &format!("{}-{}.png", name.replace(' ', "_"), preset));
+
+    // a little margin around the attractor, and square so it is not stretched
+    let bounds = attractor.bounds(100000);
+    let side = bounds.w().max(bounds.h()) * 1.05;
+    let bounds = nannou::geom::Rect::from_x_y_w_h(bounds.x(), bounds.y(), side, side);
+
+    let mut histogram = Histogram::new(size, size, bounds);
+    histogram.accumulate(attractor.as_ref(), iterations);
+    println!("{} points, densest pixel {}", histogram.total(), histogram.max());
+
+    save_png(&histogram, &ToneMap::default(), &Gradient::default(), &output).expect("could not write the image");
+    println!("saved {}", output);
+}
@@ -0,0 +1,34 @@
+// Looks for parameters that give good looking attractors and adds them to the presets file
+//
+//     cargo run --release --bin attractor_search -- [attractor] [count] [tries] [presets file]
+//
+// The presets file defaults to attractor_presets.txt in the working directory.
+// clifford-attractors picks them up, L cycles through them
+
+use std::{env, process};
+use nannou_utils::attractor::ATTRACTOR_NAMES;
+use nannou_utils::attractor_search::{save_presets, search, SearchParams, PRESETS_FILE};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let name = args.first().cloned().unwrap_or_else(|| "clifford".to_string());
+    if !ATTRACTOR_NAMES.contains(&name.as_str()) {
+        eprintln!("unknown attractor {}, pick one of {:?}", name, ATTRACTOR_NAMES);
+        process::exit(1);
+    }
+    let count: usize = args.get(1).map(|v| v.parse().expect("count should be a number")).unwrap_or(10);
+    let tries: usize = args.get(2).map(|v| v.parse().expect("tries should be a number")).unwrap_or(10000);
+    let path = args.get(3).map(String::as_str).unwrap_or(PRESETS_FILE);
+
+    let found = search(&name, count, tries, &SearchParams::default());
+    if found.is_empty() {
+        println!("nothing found, try more tries");
+        return;
+    }
+    for params in found.iter() {
+        println!("{}: {:?}", name, params);
+    }
+    let presets: Vec<(String, Vec<f32>)> = found.into_iter().map(|p| (name.clone(), p)).collect();
+    save_presets(path, &presets).expect("could not write the presets");
+    println!("{} presets added to {}", presets.len(), path);
+}
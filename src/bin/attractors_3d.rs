@@ -0,0 +1,141 @@
+use std::collections::VecDeque;
+use nannou::prelude::*;
+use nannou_utils::get_random_night;
+use nannou_utils::attractor::{continuous_by_name, ContinuousAttractor, CONTINUOUS_ATTRACTOR_NAMES};
+use nannou_utils::camera::{Camera, Projection};
+use nannou_utils::ode::{Integrator, Stepper};
+
+const TRAIL_COUNT: usize = 30;
+const TRAIL_LENGTH: usize = 300;
+// simulated time per frame, before the attractor's own time scale
+const FRAME_TIME: f32 = 0.01;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .view(view)
+        .run();
+}
+
+struct Trail {
+    head: Vec3,
+    points: VecDeque<Vec3>,
+    color: Srgba<u8>,
+}
+
+struct Model {
+    attractor: Box<dyn ContinuousAttractor + Send + Sync>,
+    attractor_index: usize,
+    integrator: Integrator,
+    trails: Vec<Trail>,
+    camera: Camera,
+    spin: bool,
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1000, 800)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    let attractor = continuous_by_name(CONTINUOUS_ATTRACTOR_NAMES[0]).unwrap();
+    let mut model = Model {
+        attractor,
+        attractor_index: 0,
+        integrator: Integrator::rk4(0.005),
+        trails: vec![],
+        camera: Camera::new(Vec3::ZERO, 100., 10.),
+        spin: true,
+    };
+    reset(&mut model, app.window_rect().wh());
+    model
+}
+
+// New trails around the start, and a camera that fits the whole attractor
+fn reset(model: &mut Model, size: Vec2) {
+    let attractor = &model.attractor;
+    let f = |p: Vec3| attractor.derivative(p);
+    let mut integrator = Integrator::rk4(0.005);
+    let mut p = attractor.start();
+    let mut samples = vec![];
+    for _ in 0..2000 {
+        p = integrator.advance(&f, p, FRAME_TIME * attractor.time_scale());
+        samples.push(p);
+    }
+    model.camera.fit(&samples[200..], size * 0.9);
+
+    model.trails = (0..TRAIL_COUNT).map(|ii| Trail {
+        // spread along the orbit so they don't all start in the same place
+        head: samples[200 + ii * 50 % 1800] + vec3(random_range(-0.01, 0.01), 0., 0.),
+        points: VecDeque::with_capacity(TRAIL_LENGTH),
+        color: get_random_night(Some(255)),
+    }).collect();
+}
+
+// A next attractor, I switches between RK4 and adaptive RK45,
+// P between orthographic and perspective, Space stops the camera
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    match key {
+        Key::A => {
+            model.attractor_index = (model.attractor_index + 1) % CONTINUOUS_ATTRACTOR_NAMES.len();
+            model.attractor = continuous_by_name(CONTINUOUS_ATTRACTOR_NAMES[model.attractor_index]).unwrap();
+            reset(model, app.window_rect().wh());
+        }
+        Key::I => {
+            model.integrator = match model.integrator.stepper {
+                Stepper::Rk4 { .. } => Integrator::rk45(1e-4),
+                Stepper::Rk45 { .. } => Integrator::rk4(0.005),
+            };
+        }
+        Key::P => {
+            let projection = match model.camera.projection {
+                Projection::Orthographic => Projection::Perspective,
+                Projection::Perspective => Projection::Orthographic,
+            };
+            model.camera.set_projection(projection);
+        }
+        Key::Space => model.spin = !model.spin,
+        _ => {}
+    }
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    let attractor = &model.attractor;
+    let f = |p: Vec3| attractor.derivative(p);
+    let duration = FRAME_TIME * attractor.time_scale();
+    for trail in model.trails.iter_mut() {
+        trail.head = model.integrator.advance(&f, trail.head, duration);
+        trail.points.push_back(trail.head);
+        if trail.points.len() > TRAIL_LENGTH {
+            trail.points.pop_front();
+        }
+    }
+    if model.spin {
+        model.camera.rotate(0.003, 0.);
+    }
+    // the mouse tilts the camera, through rotate so it stays clamped when the mouse leaves the window
+    let r = app.window_rect();
+    let pitch = map_range(app.mouse.y, r.bottom(), r.top(), -1.2, 1.2);
+    model.camera.rotate(0., pitch - model.camera.pitch);
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    frame.clear(BLACK);
+    for trail in model.trails.iter() {
+        // older points fade out
+        let n = trail.points.len().max(1) as f32;
+        let points = trail.points.iter().enumerate().filter_map(|(ii, p)| {
+            model.camera.project(*p).map(|(s, _)| {
+                let mut c = trail.color;
+                c.alpha = (ii as f32 / n * 255.) as u8;
+                (s, c)
+            })
+        });
+        draw.polyline().weight(1.5).points_colored(points);
+    }
+    draw.text(&format!("{} - {}", model.attractor.name(), model.integrator.stepper.name()))
+        .xy(app.window_rect().top_left() + vec2(80., -20.))
+        .color(WHITE);
+    draw.to_frame(app, &frame).unwrap();
+}
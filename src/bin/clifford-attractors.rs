@@ -1,18 +1,76 @@
+use nannou::noise::NoiseFn;
 use nannou::prelude::*;
-use nannou_utils::{draw_soft_bg, get_random_color, get_random_green, Pt, srgba8_t};
+use nannou_utils::NIGHT_PALETTE;
+use nannou_utils::attractor::{by_name, Attractor, ParamMorph, ATTRACTOR_NAMES};
+use nannou_utils::attractor_search::{load_presets, PRESETS_FILE};
+use nannou_utils::density::Gradient;
+use nannou_utils::noise_source::{NoiseKind, NoiseSource};
 
-const A: f32 = 1.5;
-const B: f32 = -1.8;
-const C: f32 = 1.6;
-const D: f32 = 0.9;
+const POINT_COUNT: usize = 1000;
+// points are started over after this many steps, so there is always a mix of ages
+const MAX_AGE: u32 = 600;
+const MORPH_TIME: f32 = 3.;
+// how long a preset stays before moving on to the next one
+const HOLD_TIME: f32 = 2.;
+// how far the noise drifts the parameters, relative to their size
+const DRIFT: f32 = 0.15;
+const NUDGE: f32 = 0.01;
 
 fn main() {
-    nannou::app(model).update(update).simple_window(view).run();
+    nannou::app(model).update(update).run();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Animation {
+    Still,
+    // goes through the presets of the attractor one after the other
+    Presets,
+    // wanders around the current parameters
+    Noise,
+}
+
+impl Animation {
+    fn next(self) -> Self {
+        match self {
+            Animation::Still => Animation::Presets,
+            Animation::Presets => Animation::Noise,
+            Animation::Noise => Animation::Still,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Coloring {
+    Velocity,
+    Age,
+}
+
+struct Dot {
+    p: Vec2,
+    // how far it jumped in the last step
+    speed: f32,
+    age: u32,
 }
 
 struct Model {
-    points: Pt,
-    h: f32,
+    dots: Vec<Dot>,
+    attractor: Box<dyn Attractor + Send + Sync>,
+    attractor_index: usize,
+    preset: usize,
+    // where the attractor lives, mapped to the window
+    bounds: Rect,
+    // found by the attractor_search bin, if it was ever run
+    saved: Vec<(String, Vec<f32>)>,
+    saved_index: usize,
+    morph: ParamMorph,
+    animation: Animation,
+    // time since the last morph ended
+    hold: f32,
+    noise: NoiseSource,
+    coloring: Coloring,
+    gradient: Gradient,
+    // the parameter the arrow keys and the mouse change
+    selected: usize,
 }
 
 fn get_random_point() -> Vec2 {
@@ -21,54 +79,191 @@ fn get_random_point() -> Vec2 {
         random_range(-2.0, 2.0),
     )
 }
-fn model(_app: &App) -> Model {
-    let points = vec![
-        get_random_point()
-    ];
-    Model { points, h: random() }
+
+fn new_dot(attractor: &dyn Attractor) -> Dot {
+    Dot {
+        p: attractor.start() + get_random_point() * 0.01,
+        speed: 0.,
+        // so they don't all start over together
+        age: random_range(0, MAX_AGE),
+    }
 }
 
-fn de_jong(p: &Vec2) -> Vec2 {
-    vec2 (
-        (A * p.y).sin() - (B * p.x).cos(),
-        (C * p.x).sin() - (D * p.y).cos()
-    )
+fn model(app: &App) -> Model {
+    app.new_window()
+        .key_pressed(key_pressed)
+        .view(view)
+        .build()
+        .unwrap();
+    let mut attractor = by_name("clifford").unwrap();
+    attractor.use_preset("sketch");
+    let bounds = attractor.bounds(10000);
+    // the presets attractor_search found, from the working directory unless a path is given
+    let path = std::env::args().nth(1).unwrap_or_else(|| PRESETS_FILE.to_string());
+    let saved = load_presets(path).unwrap_or_default();
+    let morph = ParamMorph::new(&attractor.params(), MORPH_TIME);
+    Model {
+        dots: vec![],
+        attractor,
+        attractor_index: 0,
+        preset: 1,
+        bounds,
+        saved,
+        saved_index: 0,
+        morph,
+        animation: Animation::Still,
+        hold: 0.,
+        noise: NoiseSource::new(NoiseKind::Perlin),
+        coloring: Coloring::Velocity,
+        gradient: Gradient::from_palette(&NIGHT_PALETTE),
+        selected: 0,
+    }
 }
 
-fn clifford_attractors(p: &Vec2) -> Vec2 {
-    vec2 (
-        (A * p.y).sin() + C * (A * p.x).cos(),
-        (B * p.x).sin() + D * (B * p.y).cos()
-    )
+fn switch_attractor(model: &mut Model, index: usize) {
+    model.attractor_index = index;
+    model.attractor = by_name(ATTRACTOR_NAMES[index]).unwrap();
+    model.morph.jump_to(&model.attractor.params());
+    model.preset = 0;
+    model.selected = 0;
+    model.bounds = model.attractor.bounds(10000);
+    // start over from the new attractor's own start, the old points may escape
+    model.dots.clear();
 }
 
-fn update(app: &App, model: &mut Model, _update: Update) {
-    // xn+1 = sin(a * yn) + c * cos(a xn)
-    // yn+1 = sin(b * xn) + d * cos(b yn)
+fn next_preset(model: &mut Model) {
+    let presets = model.attractor.presets();
+    model.preset = (model.preset + 1) % presets.len();
+    model.morph.morph_to(presets[model.preset].1);
+}
 
-    if model.points.len() < 1000 {
-        model.points.push(get_random_point());
+// A switches to the next attractor, P morphs to the next preset of the current one,
+// L to the next preset loaded from the presets file, M cycles still, presets and noise animation,
+// C colors by velocity or age, Up/Down pick a parameter and Left/Right nudge it.
+// Dragging the mouse bends the picked parameter and the one after it.
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    let count = model.attractor.param_names().len();
+    match key {
+        Key::A => switch_attractor(model, (model.attractor_index + 1) % ATTRACTOR_NAMES.len()),
+        Key::P => next_preset(model),
+        Key::L => {
+            if model.saved.is_empty() {
+                return;
+            }
+            let (name, params) = model.saved[model.saved_index].clone();
+            model.saved_index = (model.saved_index + 1) % model.saved.len();
+            let index = match ATTRACTOR_NAMES.iter().position(|n| *n == name) {
+                Some(index) => index,
+                None => return,
+            };
+            if index != model.attractor_index {
+                switch_attractor(model, index);
+            }
+            model.morph.morph_to(&params);
+        }
+        Key::M => {
+            model.animation = model.animation.next();
+            model.hold = 0.;
+        }
+        Key::C => {
+            model.coloring = match model.coloring {
+                Coloring::Velocity => Coloring::Age,
+                Coloring::Age => Coloring::Velocity,
+            };
+        }
+        Key::Up => model.selected = (model.selected + count - 1) % count,
+        Key::Down => model.selected = (model.selected + 1) % count,
+        Key::Left | Key::Right => {
+            let mut target = model.morph.target().to_vec();
+            target[model.selected] += if key == Key::Left { -NUDGE } else { NUDGE };
+            model.morph.retarget(&target);
+        }
+        _ => {}
     }
+}
+
+// The parameters for this frame, the morph plus noise drift and the mouse
+fn current_params(app: &App, model: &Model) -> Vec<f32> {
+    let mut params = model.morph.current();
+    if model.animation == Animation::Noise {
+        let t = app.time as f64 * 0.1;
+        for (ii, v) in params.iter_mut().enumerate() {
+            *v += model.noise.get([t, ii as f64 * 7.3]) as f32 * v.abs().max(0.5) * DRIFT;
+        }
+    }
+    if app.mouse.buttons.left().is_down() {
+        let r = app.window_rect();
+        let count = params.len();
+        params[model.selected] += map_range(app.mouse.x, r.left(), r.right(), -0.5, 0.5);
+        params[(model.selected + 1) % count] += map_range(app.mouse.y, r.bottom(), r.top(), -0.5, 0.5);
+    }
+    params
+}
 
-    model.points.iter_mut().for_each(|p| {
-        *p = clifford_attractors(&p)
+fn update(app: &App, model: &mut Model, update: Update) {
+    let dt = update.since_last.as_secs_f32();
+    model.morph.update(dt);
+    if model.animation == Animation::Presets && model.morph.is_done() {
+        model.hold += dt;
+        if model.hold > HOLD_TIME {
+            model.hold = 0.;
+            next_preset(model);
+        }
+    }
+    let params = current_params(app, model);
+    model.attractor.set_params(&params);
+
+    // follow the attractor as it changes shape, but smoothly and not when it blows up
+    let target = model.attractor.bounds(2000);
+    if target.w().is_finite() && target.h().is_finite() && target.w().max(target.h()) < 1000. {
+        let b = model.bounds;
+        let l = |a: f32, b: f32| a + (b - a) * 0.1;
+        model.bounds = Rect::from_x_y_w_h(l(b.x(), target.x()), l(b.y(), target.y()), l(b.w(), target.w()), l(b.h(), target.h()));
+    }
+
+    let attractor = model.attractor.as_ref();
+    if model.dots.len() < POINT_COUNT {
+        model.dots.push(new_dot(attractor));
+    }
+    model.dots.iter_mut().for_each(|dot| {
+        let next = attractor.step(dot.p);
+        dot.age += 1;
+        if !next.is_finite() || dot.age > MAX_AGE {
+            *dot = new_dot(attractor);
+            dot.age = 0;
+            return;
+        }
+        dot.speed = next.distance(dot.p);
+        dot.p = next;
     });
-    model.h = random();
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
     let r = app.window_rect();
+    let b = model.bounds;
+    let diagonal = b.w().hypot(b.h()).max(1e-6);
 
-    for p in model.points.clone() {
-        let xn = map_range(p.x, -3., 3., r.left(), r.right());
-        let yn = map_range(p.y, -3., 3., r.top(), r.bottom());
-        draw.ellipse().radius(0.2).color(hsla(
-            model.h,
-            0.72,
-            0.80,
-            0.8,
-        )).x_y(xn, yn);
+    for dot in model.dots.iter() {
+        let xn = map_range(dot.p.x, b.left(), b.right(), r.left(), r.right());
+        let yn = map_range(dot.p.y, b.bottom(), b.top(), r.top(), r.bottom());
+        let t = match model.coloring {
+            // most jumps are well under half the attractor
+            Coloring::Velocity => dot.speed / diagonal * 2.,
+            Coloring::Age => dot.age as f32 / MAX_AGE as f32,
+        };
+        let c = model.gradient.at(t);
+        draw.ellipse().radius(0.2).color(srgba(c.red, c.green, c.blue, 204)).x_y(xn, yn);
     }
+
+    let names = model.attractor.param_names();
+    let params = model.attractor.params();
+    let text: Vec<String> = names.iter().zip(params.iter()).enumerate()
+        .map(|(ii, (n, v))| format!("{}{} {:.3}", if ii == model.selected { ">" } else { " " }, n, v))
+        .collect();
+    draw.text(&format!("{} {:?}\n{}", model.attractor.name(), model.animation, text.join("\n")))
+        .xy(r.top_left() + vec2(80., -60.))
+        .left_justify()
+        .color(WHITE);
     draw.to_frame(app, &frame).unwrap();
 }
@@ -1,8 +1,10 @@
 
-use nannou::noise::*;
 use nannou::prelude::*;
 use nannou_utils::{C8, CPt, poly_shapes, poly_shapes_colored, Pt, srgba8_t};
 use nannou_utils::ca::Ca;
+use nannou_utils::lsystem::{preset, LSystem, Module, PRESET_NAMES};
+use nannou_utils::turtle::{draw_path, fit, save_hpgl, save_svg};
+use nannou_utils::tree::{Sway, Tree, TreeParams};
 
 const DISC_COUNT: usize = 5000;
 const SPEED: f64 = 0.5;
@@ -10,8 +12,6 @@ const R: f32 = 8.;
 const GAPS: f32 = (R * 2.) + 2.;
 const RULE: i32 = 5;
 
-type NoiseAlgo = Perlin;
-
 const P1: [(u8, u8, u8); 4] = [
     (77, 69, 93),
     (233, 100, 121),
@@ -26,37 +26,22 @@ fn main() {
         .run();
 }
 
-fn branch(draw: &Draw, len: f32, theta: f32) {
-    let mut length = len;
-    // Each branch will be 2/3rds the size of the previous one
-    let sw = map_range(length, 2.0, 120.0, 1.0, 10.0);
-
-    draw.line()
-        .start(pt2(0.0, 0.0))
-        .end(pt2(0.0, length))
-        .weight(sw)
-        .color(BLACK);
-    // Move to the end of that line
-    let draw = draw.x_y(0.0, length);
-
-    length *= 0.66;
-
-    // All recursive functions must have an exit condition!!!!
-    // Here, ours is when the length of the branch is 2 pixels or less
-    if len > 2.0 {
-        let draw2 = draw.rotate(theta); // Save the current state of transformation (i.e. where are we now) and Rotate by theta
-        branch(&draw2, length, theta); // Ok, now call myself to draw two new branches!!
-
-        // Repeat the same thing, only branch off to the "left" this time!
-        let draw3 = draw.rotate(-theta);
-        branch(&draw3, length, theta);
-    }
-}
-
-
-
 struct Model {
     theta: f32,
+    preset: usize,
+    system: LSystem,
+    // expanded once, the angle only changes how it is drawn
+    modules: Vec<Module>,
+    // the natural tree instead of the L-systems
+    natural: bool,
+    tree: Tree,
+    sway: Sway,
+}
+
+fn load(index: usize) -> (LSystem, Vec<Module>) {
+    let system = preset(PRESET_NAMES[index]).unwrap();
+    let modules = system.expand(system.iterations);
+    (system, modules)
 }
 
 
@@ -65,25 +50,101 @@ fn model(app: &App) -> Model {
         .resizable(false)
         .size(600, 600)
         .resized(on_resize)
+        .key_pressed(key_pressed)
         .build()
         .unwrap();
+    let (system, modules) = load(0);
     Model {
         theta: 0.0,
+        preset: 0,
+        system,
+        modules,
+        natural: true,
+        tree: Tree::new(TreeParams::default()),
+        sway: Sway::new(0.1, 1.2, 0.4),
     }
 }
 
+// T switches between the natural tree and the L-systems, L next L-system,
+// R grows the random ones again, S saves an svg and H hpgl for a plotter of whichever is showing,
+// the natural tree as it is bent right now
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    match key {
+        Key::T => {
+            model.natural = !model.natural;
+            return;
+        }
+        Key::R if model.natural => {
+            model.tree.grow();
+            return;
+        }
+        Key::L => model.preset = (model.preset + 1) % PRESET_NAMES.len(),
+        Key::R => {}
+        Key::S | Key::H => {
+            let (segments, title) = if model.natural {
+                let pose = model.tree.pose(Vec2::ZERO, &model.sway, app.time);
+                (pose.segments(model.tree.params.bark), "natural tree")
+            } else {
+                (model.system.interpret(&model.modules), PRESET_NAMES[model.preset])
+            };
+            let name = match app.exe_name() {
+                Ok(name) => name + "_" + title.replace(' ', "_").as_str(),
+                Err(e) => {
+                    eprintln!("could not save: {}", e);
+                    return;
+                }
+            };
+            let (path, saved) = if key == Key::S {
+                let path = name + ".svg";
+                let saved = save_svg(&segments, 10., &path);
+                (path, saved)
+            } else {
+                // a 600 pixel drawing comes out 15cm wide
+                let path = name + ".hpgl";
+                let saved = save_hpgl(&segments, 10., &path);
+                (path, saved)
+            };
+            match saved {
+                Ok(()) => println!("saved {}", path),
+                Err(e) => eprintln!("could not save {}: {}", path, e),
+            }
+            return;
+        }
+        _ => return,
+    }
+    let (system, modules) = load(model.preset);
+    model.system = system;
+    model.modules = modules;
+}
+
 fn on_resize(_app: &App, model: &mut Model, new_size: Vec2) {}
 
 
 fn update(app: &App, model: &mut Model, _update: Update) {
     let win = app.window_rect();
     model.theta = map_range(app.mouse.x, win.left(), win.right(), 0.0, PI / 2.0);
+    // the mouse blows on the natural tree, up to about 70 degrees at the tips
+    model.sway.set_strength(map_range(app.mouse.x, win.left(), win.right(), 0.0, 1.2));
+    // the mouse still bends the binary tree, the others keep their own angle
+    if PRESET_NAMES[model.preset] == "binary tree" {
+        model.system.set_angle(model.theta.to_degrees());
+    }
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
     let win   = app.window_rect();
-    let draw = app.draw().x_y(0.0, win.bottom());
+    let draw = app.draw();
     frame.clear(WHITE);
-    branch(&draw, 120.0, model.theta);
+    if model.natural {
+        model.tree.display(&draw, vec2(0.0, win.bottom()), &model.sway, app.time);
+        draw.to_frame(app, &frame).unwrap();
+        return;
+    }
+    let segments = model.system.interpret(&model.modules);
+    if PRESET_NAMES[model.preset] == "binary tree" {
+        draw_path(&draw.x_y(0.0, win.bottom()), &segments);
+    } else {
+        draw_path(&draw, &fit(&segments, win.pad(20.)));
+    }
     draw.to_frame(app, &frame).unwrap();
 }
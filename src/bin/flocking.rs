@@ -0,0 +1,92 @@
+use nannou::prelude::*;
+use nannou_utils::{draw_soft_bg, get_random_blue, get_random_position};
+use nannou_utils::flocking::{Flock, FlockParams, Goal};
+use nannou_utils::particle::Particle;
+
+const BOID_COUNT: usize = 400;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .view(view)
+        .run();
+}
+
+struct Model {
+    flock: Flock,
+    boids: Vec<Particle>,
+    goal: usize,
+}
+
+fn draw_fn(draw: &Draw, location: Vec2, color: Srgba<u8>) {
+    draw.ellipse().xy(location).radius(2.5).color(color);
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1000, 800)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    let size = Vec2::new(1000., 800.);
+    let boids = (0..BOID_COUNT).map(|_| {
+        let v = vec2(random_range(-1., 1.), random_range(-1., 1.));
+        Particle::new(get_random_position(size), get_random_blue(Some(220)), v, draw_fn)
+    }).collect();
+    Model {
+        flock: Flock::new(FlockParams::default()),
+        boids,
+        goal: 0,
+    }
+}
+
+// 1, 2, 3 toggle separation, alignment and cohesion
+// G cycles what the mouse does: nothing, seek, flee, arrive
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    let defaults = FlockParams::default();
+    let params = &mut model.flock.params;
+    match key {
+        Key::Key1 => params.separation = if params.separation > 0. { 0. } else { defaults.separation },
+        Key::Key2 => params.alignment = if params.alignment > 0. { 0. } else { defaults.alignment },
+        Key::Key3 => params.cohesion = if params.cohesion > 0. { 0. } else { defaults.cohesion },
+        Key::G => model.goal = (model.goal + 1) % 4,
+        _ => {}
+    }
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    let mouse = app.mouse.position();
+    model.flock.goal = match model.goal {
+        1 => Goal::Seek(mouse),
+        2 => Goal::Flee(mouse),
+        3 => Goal::Arrive(mouse),
+        _ => Goal::None,
+    };
+    model.flock.update(&mut model.boids);
+
+    // wrap around the edges
+    let r = app.window_rect();
+    for boid in model.boids.iter_mut() {
+        let mut p = boid.location();
+        if p.x < r.left() { p.x = r.right() }
+        if p.x > r.right() { p.x = r.left() }
+        if p.y < r.bottom() { p.y = r.top() }
+        if p.y > r.top() { p.y = r.bottom() }
+        boid.set_location(p);
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    draw_soft_bg(&draw, app, BLACK, 0.1);
+    for boid in model.boids.iter() {
+        let tail = boid.location() - boid.velocity() * 3.;
+        draw.line()
+            .start(tail)
+            .end(boid.location())
+            .weight(1.)
+            .color(boid.color());
+        boid.display(&draw);
+    }
+    draw.to_frame(app, &frame).unwrap();
+}
@@ -0,0 +1,205 @@
+use nannou::image::{DynamicImage, RgbaImage};
+use nannou::prelude::*;
+use nannou::wgpu::Texture;
+use nannou_utils::attractor::{Attractor, Clifford};
+use nannou_utils::flow::{lic, FlowField, FlowMode, NoiseFlow, Octaves};
+use nannou_utils::noise_source::{NoiseKind, NoiseSource};
+use nannou_utils::streamline::{evenly_spaced, Streamline, StreamlineParams};
+
+const ARROW_SPACING: f32 = 25.;
+// the LIC texture is computed at a lower resolution than the window
+const LIC_SCALE: usize = 2;
+const LIC_LENGTH: usize = 20;
+
+type Field = Box<dyn FlowField + Send + Sync>;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .view(view)
+        .run();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Arrows,
+    Streamlines,
+    Lic,
+}
+
+struct Model {
+    fields: Vec<(&'static str, Field)>,
+    field: usize,
+    mode: Mode,
+    streamlines: Vec<Streamline>,
+    texture: Option<Texture>,
+    time: f32,
+    animate: bool,
+    // the streamlines or the texture need to be computed again
+    dirty: bool,
+}
+
+// curl noise like green_growth, the same with a few octaves and moving with time,
+// a few point vortices that also pull in or push out, where a clifford map sends
+// every point, and a plain function
+fn make_fields() -> Vec<(&'static str, Field)> {
+    let mut noise = NoiseSource::new(NoiseKind::Perlin);
+    noise.set_seed(random());
+    let mut curl = NoiseFlow::new(noise, FlowMode::Curl);
+    curl.set_scale(0.004);
+    let mut fbm = NoiseFlow::new(noise, FlowMode::Curl);
+    fbm.set_scale(0.003);
+    fbm.set_octaves(Octaves::new(4, 2., 0.5));
+    fbm.set_time_scale(0.1);
+
+    let vortices: Vec<(Vec2, f32)> = (0..4)
+        .map(|_| (vec2(random_range(-400., 400.), random_range(-300., 300.)), random_range(-1., 1.)))
+        .collect();
+    // each one swirls around and pulls in (or pushes out) by its strength
+    let vortex_field = move |p: Vec2, _time: f32| {
+        vortices.iter().fold(Vec2::ZERO, |v, (center, strength)| {
+            let d = *center - p;
+            let falloff = 1. / (d.length_squared() + 100.);
+            v + (d.perp() + d * *strength) * falloff
+        })
+    };
+    // the window covers about the box the orbits stay in, a random preset every time
+    let mut clifford = Clifford::default();
+    let presets = clifford.presets();
+    clifford.use_preset(presets[random_range(0, presets.len())].0);
+    let clifford_field = move |p: Vec2, _time: f32| {
+        let q = p * 0.006;
+        clifford.step(q) - q
+    };
+    let waves = |p: Vec2, time: f32| vec2((p.y * 0.01 + time).sin(), (p.x * 0.01).cos());
+
+    vec![
+        ("curl noise", Box::new(curl)),
+        ("fbm curl noise", Box::new(fbm)),
+        ("vortices", Box::new(vortex_field)),
+        ("clifford map", Box::new(clifford_field)),
+        ("waves", Box::new(waves)),
+    ]
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1000, 800)
+        .resized(on_resize)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    Model {
+        fields: make_fields(),
+        field: 0,
+        mode: Mode::Arrows,
+        streamlines: vec![],
+        texture: None,
+        time: 0.,
+        animate: false,
+        dirty: true,
+    }
+}
+
+// M cycles arrows, streamlines and LIC, F cycles the fields,
+// Space animates the fields that change with time, R makes new fields
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    match key {
+        Key::M => {
+            model.mode = match model.mode {
+                Mode::Arrows => Mode::Streamlines,
+                Mode::Streamlines => Mode::Lic,
+                Mode::Lic => Mode::Arrows,
+            };
+        }
+        Key::F => model.field = (model.field + 1) % model.fields.len(),
+        Key::Space => model.animate = !model.animate,
+        Key::R => model.fields = make_fields(),
+        _ => return,
+    }
+    model.dirty = true;
+}
+
+fn on_resize(_app: &App, model: &mut Model, _new_size: Vec2) {
+    model.dirty = true;
+}
+
+fn update(app: &App, model: &mut Model, update: Update) {
+    if model.animate {
+        model.time += update.since_last.as_secs_f32();
+        // the texture is too slow to follow every frame
+        if model.mode != Mode::Lic {
+            model.dirty = true;
+        }
+    }
+    if !model.dirty {
+        return;
+    }
+    model.dirty = false;
+    let r = app.window_rect();
+    let field = &model.fields[model.field].1;
+    match model.mode {
+        Mode::Arrows => {}
+        Mode::Streamlines => {
+            model.streamlines = evenly_spaced(field.as_ref(), r, model.time, &StreamlineParams::default());
+        }
+        Mode::Lic => {
+            let (w, h) = (r.w() as usize / LIC_SCALE, r.h() as usize / LIC_SCALE);
+            let values = lic(field.as_ref(), r, w, h, LIC_LENGTH, model.time);
+            let image = RgbaImage::from_fn(w as u32, h as u32, |x, y| {
+                let v = (values[y as usize * w + x as usize] * 255.) as u8;
+                nannou::image::Rgba([v, v, v, 255])
+            });
+            model.texture = Some(Texture::from_image(app, &DynamicImage::ImageRgba8(image)));
+        }
+    }
+}
+
+fn draw_arrows(draw: &Draw, r: Rect, field: &Field, time: f32) {
+    let mut y = r.bottom() + ARROW_SPACING / 2.;
+    while y < r.top() {
+        let mut x = r.left() + ARROW_SPACING / 2.;
+        while x < r.right() {
+            let p = vec2(x, y);
+            let v = field.sample(p, time).normalize_or_zero() * ARROW_SPACING * 0.4;
+            draw.arrow()
+                .start(p - v)
+                .end(p + v)
+                .weight(1.)
+                .head_length(4.)
+                .head_width(3.)
+                .color(srgba(203u8, 241, 245, 200));
+            x += ARROW_SPACING;
+        }
+        y += ARROW_SPACING;
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    frame.clear(BLACK);
+    let r = app.window_rect();
+    let (name, field) = &model.fields[model.field];
+
+    match model.mode {
+        Mode::Arrows => draw_arrows(&draw, r, field, model.time),
+        Mode::Streamlines => {
+            for line in model.streamlines.iter() {
+                draw.polyline()
+                    .weight(1.5)
+                    .points(line.points.iter().copied())
+                    .color(srgba(203u8, 241, 245, 200));
+            }
+        }
+        Mode::Lic => {
+            if let Some(texture) = model.texture.as_ref() {
+                draw.texture(texture).wh(r.wh());
+            }
+        }
+    }
+
+    draw.text(&format!("{} - {:?}", name, model.mode))
+        .xy(r.top_left() + vec2(110., -20.))
+        .color(WHITE);
+    draw.to_frame(app, &frame).unwrap();
+}
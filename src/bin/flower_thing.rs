@@ -1,13 +1,15 @@
 use nannou::ease::sine::{ease_in, ease_in_out};
 use nannou::lyon::lyon_tessellation::LineCap;
-use nannou::noise::*;
+use nannou::noise::NoiseFn;
 use nannou::prelude::*;
 use nannou_utils::{C8, CPt, poly_shapes, poly_shapes_colored, Pt, srgba8_t};
 use nannou_utils::ca::Ca;
+use nannou_utils::noise_source::{NoiseKind, NoiseSource};
 
 
 const POINTS: u32 = 2000;
-type NoiseAlgo = Perlin;
+// how far the noise pushes a point in or out, relative to its distance from the center
+const WOBBLE: f32 = 0.15;
 
 fn main() {
     nannou::app(model)
@@ -18,7 +20,7 @@ fn main() {
 
 
 struct Model {
-    noise: NoiseAlgo,
+    noise: NoiseSource,
     loc: u32
 }
 
@@ -28,14 +30,31 @@ fn model(app: &App) -> Model {
         .resizable(false)
         .size(600, 600)
         .resized(on_resize)
+        .key_pressed(key_pressed)
         .build()
         .unwrap();
+    let mut noise = NoiseSource::new(NoiseKind::Perlin);
+    noise.set_seed(random());
     Model {
-        noise: Perlin::new(),
+        noise,
         loc: 1,
     }
 }
 
+// N switches to the next kind of noise and starts the flower again
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    if key == Key::N {
+        model.noise.set_kind(model.noise.kind.next());
+        model.loc = 0;
+        app.set_loop_mode(LoopMode::RefreshSync);
+    }
+}
+
+// bends the spiral a little, the same angle bends the same way
+fn wobble(noise: &NoiseSource, theta: f32, loc: u32) -> f32 {
+    1. + noise.get([theta.cos() as f64 * 2., theta.sin() as f64 * 2., loc as f64 * 0.002]) as f32 * WOBBLE
+}
+
 fn on_resize(_app: &App, model: &mut Model, new_size: Vec2) {
     if model.loc < POINTS {
         model.loc += 1;
@@ -52,18 +71,18 @@ fn update(app: &App, model: &mut Model, _update: Update) {
 fn view(app: &App, model: &Model, frame: Frame) {
 
     let draw = app.draw();
-    if frame.nth() == 0 {
+    if frame.nth() == 0 || model.loc == 1 {
         draw.background().color(BLACK);
     }
 
-    let r1 = 6.0 * (model.loc as f32).sqrt();
     let theta1 = 2.4 * model.loc as f32;
+    let r1 = 6.0 * (model.loc as f32).sqrt() * wobble(&model.noise, theta1, model.loc);
     let x1 = r1 * theta1.cos();
     let y1 = r1 * theta1.sin();
     draw.ellipse().x_y(x1, y1).color(INDIGO).w_h(9.0, 9.0);
 
-    let r0 = 6.0 * (model.loc as f32).sqrt() - 2.0;
     let theta0 = 2.4 * model.loc as f32;
+    let r0 = 6.0 * (model.loc as f32).sqrt() * wobble(&model.noise, theta0, model.loc) - 2.0;
     let x0 = r0 * theta0.cos();
     let y0 = r0 * theta0.sin();
     draw.ellipse()
@@ -0,0 +1,209 @@
+use nannou::event::{MouseScrollDelta, TouchPhase};
+use nannou::image::DynamicImage;
+use nannou::prelude::*;
+use nannou::wgpu::Texture;
+use nannou_utils::{NIGHT_PALETTE, RETRO_PALETTE};
+use nannou_utils::density::Gradient;
+use nannou_utils::fractal::{render, save_png, Coloring, Fractal, FractalKind, Viewport};
+
+// the image is computed at a lower resolution than the window while moving around
+const PREVIEW_SCALE: usize = 4;
+const EXPORT_WIDTH: usize = 3000;
+const ZOOM_STEP: f64 = 0.8;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .view(view)
+        .run();
+}
+
+struct Model {
+    fractal: Fractal,
+    viewport: Viewport,
+    coloring: Coloring,
+    palette: usize,
+    texture: Option<Texture>,
+    // the julia set follows the mouse
+    follow: bool,
+    // where the mouse was the last time the drag moved the view
+    drag: Option<Vec2>,
+    // the resolution the image has to be computed at next, none when it is up to date
+    scale: Option<usize>,
+    // frames since the last change, the full image waits for things to settle
+    idle: usize,
+}
+
+fn gradients() -> Vec<Gradient> {
+    vec![
+        Gradient::default(),
+        Gradient::from_palette(&NIGHT_PALETTE),
+        Gradient::from_palette(&RETRO_PALETTE),
+    ]
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1000, 800)
+        .key_pressed(key_pressed)
+        .mouse_pressed(mouse_pressed)
+        .mouse_released(mouse_released)
+        .mouse_moved(mouse_moved)
+        .mouse_wheel(mouse_wheel)
+        .resized(on_resize)
+        .build()
+        .unwrap();
+    let kind = FractalKind::Mandelbrot;
+    Model {
+        fractal: Fractal::new(kind),
+        viewport: Viewport::for_kind(kind),
+        coloring: Coloring::new(gradients().remove(0)),
+        palette: 0,
+        texture: None,
+        follow: false,
+        drag: None,
+        scale: Some(PREVIEW_SCALE),
+        idle: 0,
+    }
+}
+
+fn changed(model: &mut Model) {
+    model.scale = Some(PREVIEW_SCALE);
+    model.idle = 0;
+}
+
+// window coordinates, the origin in the middle and y up, to the plane
+fn to_plane(app: &App, model: &Model, p: Vec2) -> (f64, f64) {
+    let scale = model.viewport.width / app.window_rect().w() as f64;
+    (
+        model.viewport.center.0 + p.x as f64 * scale,
+        model.viewport.center.1 + p.y as f64 * scale,
+    )
+}
+
+// F next fractal, J makes the julia set follow the mouse, G next palette,
+// Up/Down more or fewer iterations, [ and ] shift the colors, R resets the view,
+// S saves a large image, it renders on this thread so the window freezes until it is done.
+// Drag to pan, scroll to zoom.
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    match key {
+        Key::F => {
+            let kind = model.fractal.kind.next();
+            model.fractal.set_kind(kind);
+            model.viewport = Viewport::for_kind(kind);
+        }
+        Key::J => model.follow = !model.follow,
+        Key::G => {
+            let mut gradients = gradients();
+            model.palette = (model.palette + 1) % gradients.len();
+            model.coloring.gradient = gradients.remove(model.palette);
+        }
+        Key::Up => model.fractal.set_max_iterations(model.fractal.max_iterations * 2),
+        Key::Down => model.fractal.set_max_iterations(model.fractal.max_iterations / 2),
+        Key::LBracket => model.coloring.period = (model.coloring.period / 1.5).max(2.),
+        Key::RBracket => model.coloring.period *= 1.5,
+        Key::R => model.viewport = Viewport::for_kind(model.fractal.kind),
+        Key::S => {
+            let r = app.window_rect();
+            let h = (EXPORT_WIDTH as f32 * r.h() / r.w()) as usize;
+            let name = match app.exe_name() {
+                Ok(name) => name,
+                Err(e) => {
+                    eprintln!("could not save: {}", e);
+                    return;
+                }
+            };
+            let path = name + "_" + model.fractal.kind.name().replace(' ', "_").as_str() + ".png";
+            match save_png(&model.fractal, &model.viewport, &model.coloring, EXPORT_WIDTH, h, &path) {
+                Ok(()) => println!("saved {}", path),
+                Err(e) => eprintln!("could not save {}: {}", path, e),
+            }
+            return;
+        }
+        _ => return,
+    }
+    changed(model);
+}
+
+fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
+    if button == MouseButton::Left {
+        model.drag = Some(app.mouse.position());
+    }
+}
+
+fn mouse_released(_app: &App, model: &mut Model, button: MouseButton) {
+    if button == MouseButton::Left {
+        model.drag = None;
+    }
+}
+
+fn mouse_moved(app: &App, model: &mut Model, pos: Point2) {
+    if let Some(start) = model.drag {
+        let scale = model.viewport.width / app.window_rect().w() as f64;
+        let d = start - pos;
+        model.viewport.pan(d.x as f64 * scale, d.y as f64 * scale);
+        model.drag = Some(pos);
+        changed(model);
+    } else if model.follow && model.fractal.kind == FractalKind::Julia {
+        // the whole window covers the interesting part of the mandelbrot set
+        let r = app.window_rect();
+        let re = map_range(pos.x, r.left(), r.right(), -2., 1.) as f64;
+        let im = map_range(pos.y, r.bottom(), r.top(), -1.2, 1.2) as f64;
+        model.fractal.set_julia(re, im);
+        changed(model);
+    }
+}
+
+fn mouse_wheel(app: &App, model: &mut Model, delta: MouseScrollDelta, _phase: TouchPhase) {
+    let amount = match delta {
+        MouseScrollDelta::LineDelta(_, y) => y as f64,
+        MouseScrollDelta::PixelDelta(p) => p.y / 50.,
+    };
+    if amount == 0. {
+        return;
+    }
+    let (x, y) = to_plane(app, model, app.mouse.position());
+    model.viewport.zoom_at(x, y, ZOOM_STEP.powf(amount));
+    changed(model);
+}
+
+fn on_resize(_app: &App, model: &mut Model, _new_size: Vec2) {
+    changed(model);
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    model.idle += 1;
+    let scale = match model.scale {
+        Some(scale) => scale,
+        None => return,
+    };
+    // a quick low resolution image right away, the full one once nothing changed for a few frames
+    if scale == 1 && model.idle <= 5 {
+        return;
+    }
+    let r = app.window_rect();
+    let (w, h) = ((r.w() as usize / scale).max(1), (r.h() as usize / scale).max(1));
+    let image = render(&model.fractal, &model.viewport, &model.coloring, w, h);
+    model.texture = Some(Texture::from_image(app, &DynamicImage::ImageRgba8(image)));
+    model.scale = if scale == 1 { None } else { Some(1) };
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    let r = app.window_rect();
+    frame.clear(BLACK);
+    if let Some(texture) = model.texture.as_ref() {
+        draw.texture(texture).wh(r.wh());
+    }
+    let mut text = format!("{} - {} iterations - zoom {:.1e}",
+        model.fractal.kind.name(), model.fractal.max_iterations, 3.5 / model.viewport.width);
+    if model.fractal.kind == FractalKind::Julia {
+        text += &format!(" - c {:.4} {:+.4}i", model.fractal.julia.0, model.fractal.julia.1);
+    }
+    draw.text(&text)
+        .xy(r.top_left() + vec2(220., -20.))
+        .w(400.)
+        .left_justify()
+        .color(WHITE);
+    draw.to_frame(app, &frame).unwrap();
+}
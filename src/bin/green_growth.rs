@@ -1,12 +1,17 @@
-use nannou::noise::*;
 use nannou::prelude::*;
 use nannou_utils::{draw_soft_bg, get_random_green};
+use nannou_utils::flow::{FlowField, FlowMode, NoiseFlow};
+use nannou_utils::noise_source::{NoiseKind, NoiseSource};
+use nannou_utils::streamline::{evenly_spaced, Streamline, StreamlineParams};
 
 const DISC_COUNT: usize = 5000;
 
 const SPEED: f64 = 0.5;
+// the evenly spaced lines need a much smoother field than the curves
+const STREAMLINE_SCALE: f32 = 0.003;
+const STREAMLINE_WIDTH: f32 = 4.;
 
-type NoiseAlgo = Perlin;
+type Field = NoiseFlow<NoiseSource>;
 
 fn main() {
     nannou::app(model)
@@ -16,23 +21,6 @@ fn main() {
 }
 
 
-//Find the curl of the noise field based on on the noise value at the location of a disc
-fn compute_curl(noise: &NoiseAlgo, x: f64, y: f64) -> [f32; 2] {
-    let eps = 0.01;
-    //Find rate of change in X direction
-    let n1 = noise.get([x + eps, y]);
-    let n2 = noise.get([x - eps, y]);
-    //Average to find approximate derivative
-    let a = (n1 - n2) / (2. * eps);
-    //Find rate of change in Y direction
-    let n1 = noise.get([x, y + eps]);
-    let n2 = noise.get([x, y - eps]);
-    //Average to find approximate derivative
-    let b = (n1 - n2) / (2. * eps);
-    //Curl
-    return [b as f32, -a as f32];
-}
-
 #[derive(Debug)]
 struct Curve {
     start_x: f64,
@@ -41,13 +29,13 @@ struct Curve {
 }
 
 impl Curve {
-    pub fn get_a_curve(&self, noise: &NoiseAlgo) -> Vec<(Point2, Srgba<u8>)> {
+    pub fn get_a_curve(&self, field: &Field) -> Vec<(Point2, Srgba<u8>)> {
         let mut positions = vec![(Point2::new(self.start_x as f32, self.start_y as f32), self.color)];
         let mut c = self.color;
         for ii in 0..15 {
             let last = positions.last().clone().unwrap();
-            let [x, y] = compute_curl(noise, last.0.x as f64 / SPEED, last.0.y as f64 / SPEED);
-            positions.push((Point2::new(last.0.x + x, last.0.y + y), c.clone()));
+            let v = field.sample(last.0 / SPEED as f32, 0.);
+            positions.push((last.0 + v, c.clone()));
             c.alpha -= ii;
         }
         positions
@@ -57,8 +45,11 @@ impl Curve {
 struct Model {
     discs: Vec<Curve>,
     poly_points: Vec<Vec<(Point2, Srgba<u8>)>>,
-    noise: NoiseAlgo,
+    field: Field,
     size: Vec2,
+    // evenly spaced streamlines instead of the random curves
+    evenly: bool,
+    streamlines: Vec<(Streamline, Srgba<u8>)>,
 }
 
 fn get_random_position(size: Vec2) -> (f64, f64) {
@@ -71,32 +62,73 @@ fn get_random_position(size: Vec2) -> (f64, f64) {
 fn model(app: &App) -> Model {
     app.new_window()
         .resized(on_resize)
+        .key_pressed(key_pressed)
         .build()
         .unwrap();
 
     let size = Vec2::new(512., 512.);
-    let mut simplex = NoiseAlgo::new();
-    simplex.set_seed(random());
-    let (discs, poly_points) = prepare_drawing_things(&simplex,  size.clone());
-    Model { discs, poly_points, noise: simplex, size}
+    let mut noise = NoiseSource::new(NoiseKind::Perlin);
+    noise.set_seed(random());
+    let field = NoiseFlow::new(noise, FlowMode::Curl);
+    let (discs, poly_points) = prepare_drawing_things(&field, size.clone());
+    Model { discs, poly_points, field, size, evenly: false, streamlines: vec![] }
+}
+
+// E switches between the random curves and the evenly spaced streamlines,
+// N switches to the next kind of noise
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    match key {
+        Key::E => model.evenly = !model.evenly,
+        Key::N => {
+            let noise = &mut model.field.noise;
+            noise.set_kind(noise.kind.next());
+            let (discs, poly_points) = prepare_drawing_things(&model.field, model.size);
+            model.discs = discs;
+            model.poly_points = poly_points;
+        }
+        _ => return,
+    }
+    if model.evenly {
+        model.streamlines = make_streamlines(&model.field, app.window_rect());
+    }
 }
 
-fn on_resize(_app: &App, model: &mut Model, new_size: Vec2) {
+// lines keep their distance from each other, and stop when they run into another one
+fn make_streamlines(field: &Field, rect: Rect) -> Vec<(Streamline, Srgba<u8>)> {
+    // test_ratio 1 stops a line as soon as it comes within separation of another one
+    let params = StreamlineParams {
+        separation: 10.,
+        test_ratio: 1.,
+        max_steps: 300,
+        min_points: 10,
+        ..Default::default()
+    };
+    let scaled = |p: Vec2, time: f32| field.sample(p * STREAMLINE_SCALE, time);
+    evenly_spaced(&scaled, rect, 0., &params)
+        .into_iter()
+        .map(|line| (line, get_random_green(Some(244))))
+        .collect()
+}
+
+fn on_resize(app: &App, model: &mut Model, new_size: Vec2) {
     model.size = new_size;
-    let (discs, poly_points) = prepare_drawing_things(&model.noise, new_size);
+    if model.evenly {
+        model.streamlines = make_streamlines(&model.field, app.window_rect());
+    }
+    let (discs, poly_points) = prepare_drawing_things(&model.field, new_size);
     model.discs = discs;
     model.poly_points = poly_points;
 
 }
 
-fn prepare_drawing_things(noise: &NoiseAlgo, new_size: Vec2) -> (Vec<Curve>, Vec<Vec<(Point2, Srgba<u8>)>>) {
+fn prepare_drawing_things(field: &Field, new_size: Vec2) -> (Vec<Curve>, Vec<Vec<(Point2, Srgba<u8>)>>) {
     let mut discs = vec![];
     let mut poly_points = vec![];
     for ii in 0..DISC_COUNT {
         let c = get_random_green(Some(244));
         let (x, y) = get_random_position(Vec2::new(new_size.x, new_size.y));
         let disc = Curve { start_x: x, start_y: y, color: c };
-        poly_points.push(disc.get_a_curve(&noise));
+        poly_points.push(disc.get_a_curve(field));
         discs.push(disc);
     }
     (discs, poly_points)
@@ -104,17 +136,37 @@ fn prepare_drawing_things(noise: &NoiseAlgo, new_size: Vec2) -> (Vec<Curve>, Vec
 
 
 fn update(_app: &App, model: &mut Model, _update: Update) {
+    if model.evenly {
+        return;
+    }
     model.poly_points.clear();
     for disc in model.discs.iter_mut() {
-        let [x, y] = compute_curl(&model.noise, disc.start_x / 0.4, disc.start_y / 0.4);
-        disc.start_x = disc.start_x + x as f64;
-        disc.start_y = disc.start_y + y as f64;
-        model.poly_points.push(disc.get_a_curve(&model.noise));
+        let p = vec2(disc.start_x as f32, disc.start_y as f32);
+        let v = model.field.sample(p / 0.4, 0.);
+        disc.start_x = disc.start_x + v.x as f64;
+        disc.start_y = disc.start_y + v.y as f64;
+        model.poly_points.push(disc.get_a_curve(&model.field));
     }
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
+    if model.evenly {
+        draw.background().color(BLACK);
+        // thick in the middle, thin at the ends
+        for (line, color) in model.streamlines.iter() {
+            for (ii, w) in line.points.windows(2).enumerate() {
+                draw.line()
+                    .start(w[0])
+                    .end(w[1])
+                    .weight(STREAMLINE_WIDTH * line.taper(ii).max(0.1))
+                    .caps_round()
+                    .color(*color);
+            }
+        }
+        draw.to_frame(app, &frame).unwrap();
+        return;
+    }
     draw_soft_bg(&draw, app, BLACK, 0.01);
     //draw_background_grid(app, &draw);
     //draw.background().color(BLACK);
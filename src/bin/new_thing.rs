@@ -1,6 +1,3 @@
-#![feature(fn_traits)]
-
-use nannou::noise::*;
 use nannou::prelude::*;
 use nannou_utils::{C8, CPt, get_random_blue, get_random_color, get_random_green, get_random_night, get_random_retro, poly_shapes, poly_shapes_colored, Pt, srgba8_t};
 use nannou_utils::ca::Ca;
@@ -11,8 +8,6 @@ const R: f32 = 8.;
 const GAPS: f32 = (R * 2.) + 2.;
 const RULE: i32 = 5;
 
-type NoiseAlgo = Perlin;
-
 const P1: [(u8, u8, u8); 4] = [
     (77, 69, 93),
     (233, 100, 121),
@@ -0,0 +1,103 @@
+use nannou::prelude::*;
+use nannou_utils::{draw_soft_bg, get_random_position, srgba8_t};
+use nannou_utils::forces::{apply_gravity_barnes_hut, apply_repulsion, ParticleLife};
+use nannou_utils::particle::Particle;
+
+const PARTICLE_COUNT: usize = 1500;
+
+const COLORS: [(u8, u8, u8); 5] = [
+    (255, 135, 135),
+    (248, 196, 180),
+    (229, 235, 178),
+    (188, 226, 158),
+    (39, 225, 193),
+];
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .view(view)
+        .run();
+}
+
+struct Model {
+    life: ParticleLife,
+    particles: Vec<Particle>,
+    classes: Vec<usize>,
+    masses: Vec<f32>,
+    // false for particle life, true for n-body gravity
+    gravity: bool,
+}
+
+fn draw_fn(draw: &Draw, location: Vec2, color: Srgba<u8>) {
+    draw.ellipse().xy(location).radius(2.).color(color);
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1000, 1000)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    let size = Vec2::new(1000., 1000.);
+    let mut particles = vec![];
+    let mut classes = vec![];
+    let mut masses = vec![];
+    for _ii in 0..PARTICLE_COUNT {
+        let class = random_range(0, COLORS.len());
+        let c = srgba8_t(COLORS[class], 230);
+        particles.push(Particle::new(get_random_position(size), c, Vec2::ZERO, draw_fn));
+        classes.push(class);
+        masses.push(random_range(0.5, 3.));
+    }
+    Model {
+        life: ParticleLife::new(COLORS.len(), 80.),
+        particles,
+        classes,
+        masses,
+        gravity: false,
+    }
+}
+
+// R picks new attraction rules, M switches between particle life and gravity
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    match key {
+        Key::R => model.life.randomize(),
+        Key::M => {
+            model.gravity = !model.gravity;
+            model.particles.iter_mut().for_each(|p| p.set_velocity(Vec2::ZERO));
+        }
+        _ => {}
+    }
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    if model.gravity {
+        apply_gravity_barnes_hut(&mut model.particles, &model.masses, 20., 5., 0.5);
+        apply_repulsion(&mut model.particles, 4., 1., 0.5);
+    } else {
+        model.life.apply(&mut model.particles, &model.classes);
+    }
+
+    // wrap around the edges
+    let r = app.window_rect();
+    for particle in model.particles.iter_mut() {
+        particle.limit_speed(8.);
+        particle.update();
+        let mut p = particle.location();
+        if p.x < r.left() { p.x += r.w() }
+        if p.x > r.right() { p.x -= r.w() }
+        if p.y < r.bottom() { p.y += r.h() }
+        if p.y > r.top() { p.y -= r.h() }
+        particle.set_location(p);
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    draw_soft_bg(&draw, app, BLACK, 0.2);
+    for particle in model.particles.iter() {
+        particle.display(&draw);
+    }
+    draw.to_frame(app, &frame).unwrap();
+}
@@ -1,23 +1,27 @@
-#![feature(drain_filter)]
-
 use std::ops::{Add, Mul, Sub};
-use std::time::Duration;
 use nannou::noise::*;
 use nannou::prelude::*;
 use nannou::wgpu::Texture;
 use rayon::prelude::*;
 use nannou_utils::{draw_background_grid, get_random_blue, get_random_color, get_random_green, get_random_night, get_random_position, get_random_retro, GREEN_PALATE};
+use nannou_utils::clock::SimClock;
+use nannou_utils::collision::{circle_rect, resolve};
+use nannou_utils::effect::{Effect, Effects};
+use nannou_utils::lightning::{Bolt, Flash, LightningParams};
+use nannou_utils::noise_source::NoiseSource;
 use nannou_utils::particle::Particle;
+use nannou_utils::skyline::Skyline;
+use nannou_utils::spatial::{SpatialHash, SpatialIndex};
+use nannou_utils::weather::{Precipitation, Weather, WeatherParams};
 //use ;
 
 const PARTICLE_COUNT: usize = 1000;
 const PLATFORM_COUNT: usize = 10;
+const SKYLINE_LAYERS: usize = 3;
 const MAX_LIGHTENING_ALPHA: u8 = 100;
 
 const SPEED: f64 = 0.5;
 
-type NoiseAlgo = Perlin;
-
 fn main() {
     nannou::app(model)
         .update(update)
@@ -25,204 +29,196 @@ fn main() {
         .run();
 }
 
-struct Platform {
-    pub rect: Rect,
-    pub color: Srgba<u8>,
-    pub stroke: Srgba<u8>,
-}
-
-impl Platform {
-
-    pub fn display(&self, draw: &Draw) {
-        draw.rect()
-            .xy(self.rect.xy())
-            .wh(self.rect.wh())
-            .stroke(self.stroke)
-            .stroke_weight(3.)
-            .color(self.color);
-
-        let building_r = self.rect.pad(3.);
-        let w_row = (building_r.w() / 13.) + 1.;
-        let w_col = (building_r.h() / 13.) + 1.;
-        let wh = Vec2::new(1., 1.);
-
-    }
-
-    pub fn new(rect: Rect, color: Srgba<u8>, stroke: Srgba<u8>) -> Self {
-        let d = Draw::new();
-        Self { rect, color, stroke }
-    }
-}
-
-struct Circle {
-    pub position: Vec2,
-    pub r: f32,
-    pub color: Srgba<u8>,
-}
-
 struct Model {
-    noise: NoiseAlgo,
+    noise: NoiseSource,
     particles: Vec<Particle>,
     size: Vec2,
     p: Rect,
-    explosions: Vec<Circle>,
-    platforms: Vec<Platform>,
-    lightening: Option<Vec<Point2>>,
-    lightening_steps: i32,
+    explosions: Effects,
+    skyline: Skyline,
+    // every block of the front row of buildings, what the rain hits
+    platforms: Vec<Rect>,
+    platform_index: SpatialHash,
+    lightening: Option<Bolt>,
     lightening_color: Srgba<u8>,
-    lightening_last_time: Duration,
+    flash: Flash,
+    lightening_last_time: f32,
+    clock: SimClock,
+    weather: Weather,
 }
 
 fn model(app: &App) -> Model {
     app.new_window()
         .size(1200, 1000)
         .resized(on_resize)
+        .key_pressed(key_pressed)
         .build()
         .unwrap();
     let size = Vec2::new(1200., 1000.);
-    let noise = NoiseAlgo::new();
+    let noise = NoiseSource::default();
     let mut particles = vec![];
-    let mut platforms = vec![];
 
     for _ii in 0..PARTICLE_COUNT {
-        let p = new_random_particle(size);
+        let p = new_random_particle(size, Precipitation::Rain);
         particles.push(p);
     }
 
-    for ii in 0..PLATFORM_COUNT + 1 {
-        let p = make_skyline(size, app.window_rect(), ii);
-        platforms.push(p);
-    }
+    let skyline = Skyline::new(app.window_rect(), PLATFORM_COUNT, SKYLINE_LAYERS);
+    let platforms = skyline.collision_rects();
+    let platform_index = index_platforms(&platforms);
+    let mut weather = Weather::new(WeatherParams::default());
+    weather.set_surfaces(&skyline.surfaces());
 
     Model {
         noise,
         size,
         particles,
         p: Rect::from_w_h(0., 0.),
-        explosions: vec![],
+        explosions: Effects::new(0.2),
+        skyline,
         platforms,
+        platform_index,
         lightening: None,
-        lightening_steps: 0,
-        lightening_color: srgba(203, 241, 245, 0),
-        lightening_last_time: Default::default(),
+        lightening_color: srgba(203, 241, 245, 255),
+        flash: Flash::default(),
+        lightening_last_time: 0.,
+        clock: SimClock::new(60.),
+        weather,
+    }
+}
+
+// Space pauses, S steps once while paused, Up/Down speed up or slow down the rain,
+// Left/Right change the wind, P switches between rain, snow, hail and sleet,
+// N changes the noise moving the storm around
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    let clock = &mut model.clock;
+    match key {
+        Key::N => model.noise.set_kind(model.noise.kind.next()),
+        Key::P => {
+            let kind = model.weather.precipitation.next();
+            model.weather.precipitation = kind;
+            let r = app.window_rect();
+            for particle in model.particles.iter_mut() {
+                let mut p = get_random_position(model.size);
+                p.y = random_range(r.bottom(), r.top() + 500.);
+                *particle = kind.spawn(p);
+            }
+        }
+        Key::Left => model.weather.params.wind -= 0.5,
+        Key::Right => model.weather.params.wind += 0.5,
+        Key::Space => clock.toggle_pause(),
+        Key::S => clock.single_step(),
+        Key::Up => clock.set_time_scale(clock.time_scale() * 1.5),
+        Key::Down => clock.set_time_scale(clock.time_scale() / 1.5),
+        _ => {}
     }
 }
 
 fn on_resize(app: &App, model: &mut Model, new_size: Vec2) {
     model.size = new_size;
-    model.platforms.clear();
-    for ii in 0..PLATFORM_COUNT + 1 {
-        let p = make_skyline(new_size, app.window_rect(), ii);
-        model.platforms.push(p);
-    }
+    model.skyline = Skyline::new(app.window_rect(), PLATFORM_COUNT, SKYLINE_LAYERS);
+    model.platforms = model.skyline.collision_rects();
+    model.platform_index = index_platforms(&model.platforms);
+    model.weather.set_surfaces(&model.skyline.surfaces());
 }
 
-fn draw_fn(draw: &Draw, location: Vec2, color: Srgba<u8>) {
-    let mut stroke = get_random_retro(Some(100));
-    draw.line()
-        .start(location)
-        .end(location.add(Vec2::new(0., 10.)))
-        .color(stroke);
+fn index_platforms(platforms: &[Rect]) -> SpatialHash {
+    let mut index = SpatialHash::new(64.);
+    for (ii, rect) in platforms.iter().enumerate() {
+        index.insert_rect(ii, *rect);
+    }
+    index
 }
 
-fn new_random_particle(size: Vec2) -> Particle {
+fn new_random_particle(size: Vec2, kind: Precipitation) -> Particle {
     let mut p = get_random_position(size);
     p.y = (size.y / 2.) + random_range(0., 500.);
-    let v = Vec2::new(
-        0.,
-        random_range(-9., -4.),
-    );
-    let c = get_random_retro(Some(200));
-    Particle::new(p, c, v, draw_fn)
+    kind.spawn(p)
 }
 
-fn make_skyline(size: Vec2, rect: Rect, ii: usize) -> Platform {
-    let color = srgba(27, 38, 44, 255);
-    let stroke = srgba(28, 39, 45, 225);
-    let w = rect.w() / PLATFORM_COUNT as f32;
-    let x = rect.left() + w * ii as f32;
-    let y = rect.bottom() - random_range(rect.bottom() + 20., rect.bottom() + 100.);
-    let h = random_range(100., 300.);
-    let mut rt = Rect::from_x_y_w_h(x, y, w, h);
-    Platform::new(rt.align_bottom_of(rect), color, stroke)
+// every particle wobbles and spins a little differently
+fn phase(ii: usize) -> f32 {
+    ii as f32 * 0.618 * TAU
 }
 
 
-fn update(app: &App, model: &mut Model, _update: Update) {
+fn update(app: &App, model: &mut Model, update: Update) {
+    for _ in 0..model.clock.advance(update.since_last) {
+        step(app, model);
+    }
+}
+
+fn step(app: &App, model: &mut Model) {
     let r = app.window_rect();
-    for particle in model.particles.iter_mut() {
-        particle.update();
+    let time = model.clock.time();
+    let kind = model.weather.precipitation;
+    for (jj, particle) in model.particles.iter_mut().enumerate() {
+        let wind = model.weather.wind_at(time, particle.location());
+        kind.step(particle, wind, time, phase(jj));
+
+        let mut p = particle.location();
+        if p.x < r.left() { p.x += r.w() }
+        if p.x > r.right() { p.x -= r.w() }
+        particle.set_location(p);
 
         // going out of screen
         if particle.location().y < r.bottom() {
-            update_particle(particle, &mut model.explosions, model.size);
+            let ground = vec2(particle.location().x, r.bottom());
+            model.weather.impact(time, ground, particle.velocity(), particle.color());
+            update_particle(particle, &mut model.explosions, model.size, kind);
         }
 
-        // collision with any platform?
-        for platform in model.platforms.iter() {
-            let c = Circle { position: particle.location(), r: 5., color: Default::default() };
-            if circle_rect_collision(c, platform.rect) {
-                update_particle(particle, &mut model.explosions, model.size);
+        // collision with any platform nearby?
+        for ii in model.platform_index.query_radius(particle.location(), 5.) {
+            let rect = model.platforms[ii];
+            if let Some(contact) = circle_rect(particle.location(), 5., rect) {
+                let roof = vec2(particle.location().x, rect.top());
+                let speed = particle.velocity().length();
+                // hail bounces off a few times before it stays down
+                if resolve(particle, &contact, kind.response()) && particle.velocity().length() > 2. {
+                    model.weather.splashes.spawn(roof, speed, wind, particle.color(), 1);
+                    break;
+                }
+                model.weather.impact(time, roof, particle.velocity(), particle.color());
+                update_particle(particle, &mut model.explosions, model.size, kind);
+                break;
             }
         }
     }
-    model.explosions.drain_filter(|v| {
-        v.color.alpha < 50
-    });
-    model.explosions.iter_mut().for_each(|v| {
-        v.r += 0.15;
-        v.color.alpha -= 5;
-    });
-
-    if model.lightening.is_some() {
-        let t = model.lightening_color.alpha as i32 + model.lightening_steps;
-        model.lightening_color.alpha = t as u8;
-        if model.lightening_color.alpha >= MAX_LIGHTENING_ALPHA { model.lightening_steps = -10 }
-        if model.lightening_color.alpha == 0 {
-            model.lightening = None;
-            model.lightening_steps = 0;
-        }
+    model.weather.update();
+    model.skyline.update();
+    model.explosions.update(model.clock.dt());
+
+    model.flash.update(model.clock.dt());
+    if !model.flash.is_active() {
+        model.lightening = None;
     }
 
-    let long_enough = app.duration.since_start - model.lightening_last_time > Duration::from_secs(5);
+    let long_enough = model.clock.time() - model.lightening_last_time > 5.;
     // time for a lightening
     if random::<bool>() && model.lightening.is_none() && long_enough {
-        let time = app.elapsed_frames() as f32 / 120.0;
-        let sn = 0.01 + time.cos() as f64 * 0.005;
-        let mut start_position = get_random_position(model.size);
-        start_position.y = r.top();
-
-        let mut end_position = get_random_position(model.size);
-        end_position.y = r.bottom();
-
-        let mut current_position = start_position;
-
-        let mut points = vec![];
-
-        while current_position.distance(end_position) > 30. {
-            let n = end_position.sub(current_position).normalize();
-            current_position = current_position + n + vec2(
-                model.noise.get([sn * current_position.x as f64, sn * current_position.y as f64, 0.0]) as f32,
-                model.noise.get([sn * current_position.x as f64, sn * current_position.y as f64, 1.0]) as f32,
-            );
-            points.push(current_position.clone());
-        }
-        model.lightening = Some(points);
-        model.lightening_steps = 10;
-        model.lightening_color.alpha = 0;
-        model.lightening_last_time = app.duration.since_start;
+        // the storm drifts slowly across the sky
+        let time = model.clock.time() as f64 / 20.0;
+        let x = model.noise.get([time, 0.5]) as f32 * r.w();
+        let start_position = vec2(x.max(r.left()).min(r.right()), r.top());
+        let end_position = vec2(x + random_range(-200., 200.), r.bottom());
+
+        model.lightening = Some(Bolt::generate(start_position, end_position, &LightningParams::default()));
+        model.flash.trigger();
+        model.lightening_last_time = model.clock.time();
     }
 }
 
-fn update_particle(particle: &mut Particle, explosions: &mut Vec<Circle>, size: Vec2) {
+fn update_particle(particle: &mut Particle, explosions: &mut Effects, size: Vec2, kind: Precipitation) {
     let mut color = particle.color();
     color.alpha = 150;
-    explosions.push(Circle {
-        position: particle.location(),
-        r: 2.,
-        color,
-    });
-    let p = new_random_particle(size);
+    // hail shatters, everything else just leaves a ripple
+    let effect = match kind {
+        Precipitation::Hail => Effect::burst(particle.location(), color, 4, 1.5),
+        _ => Effect::ripple(particle.location(), color),
+    };
+    explosions.push(effect);
+    let p = new_random_particle(size, kind);
     particle.set_color(p.color());
     particle.set_location(p.location());
     particle.set_velocity(p.velocity())
@@ -232,57 +228,29 @@ fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
     frame.clear(BLACK);
 
-    if let Some(lightening) = model.lightening.as_ref() {
-        draw.polyline().points(lightening.clone()).color(model.lightening_color).finish();
-    }
+    // the whole sky lights up with the flash
+    let mut sky = model.lightening_color;
+    sky.alpha = (model.flash.value() * MAX_LIGHTENING_ALPHA as f32) as u8;
+    draw.rect().wh(app.window_rect().wh()).color(sky);
 
-    if let Some(points) = model.lightening.clone() {
-        draw.polyline().weight(3.).points(points.clone()).color(model.lightening_color).finish();
+    if let Some(bolt) = model.lightening.as_ref() {
+        bolt.display(&draw, model.lightening_color, model.flash.value());
     }
 
-    for explosion in model.explosions.iter() {
-        draw.ellipse().xy(explosion.position).radius(explosion.r).color(explosion.color);
-    }
+    model.explosions.display(&draw);
 
-    for particle in model.particles.iter() {
-        particle.display(&draw);
+    let time = model.clock.time();
+    for (jj, particle) in model.particles.iter().enumerate() {
+        model.weather.precipitation.display(&draw, particle, time, phase(jj));
     }
 
-    for platform in model.platforms.iter() {
-        platform.display(&draw);
-    }
+    // looking around with the mouse moves the far buildings
+    let shift = -app.mouse.x * 0.1;
+    model.skyline.display(&draw, shift, model.flash.value(), model.lightening_color);
+
+    model.weather.display(&draw);
 
     // draw_background_grid(&app, &draw);
     draw.to_frame(app, &frame).unwrap();
 }
 
-
-fn circle_rect_collision(circle: Circle, rect: Rect) -> bool {
-    // temporary variables to set edges for testing
-    let mut test_x = circle.position.x;
-    let mut test_y = circle.position.y;
-
-    // which edge is closest?
-    if circle.position.x < rect.left() {
-        test_x = rect.left();      // test left edge
-    } else if circle.position.x > rect.right() {
-        test_x = rect.right();
-    }  // right edge
-
-    if circle.position.y < rect.top() {
-        test_y = rect.top();      // top edge
-    } else if circle.position.y > rect.bottom() {
-        test_y = rect.bottom();
-    }
-
-    // get distance from closest edges
-    let dist_x = circle.position.x - test_x;
-    let dist_y = circle.position.y - test_y;
-    let distance = ((dist_x * dist_x) + (dist_y * dist_y));
-
-    // if the distance is less than the radius, collision!
-    if distance <= circle.r {
-        return true;
-    }
-    return false;
-}
\ No newline at end of file
@@ -0,0 +1,106 @@
+use nannou::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Orthographic,
+    // things at the target keep the size they have in orthographic
+    Perspective,
+}
+
+// Looks at target from distance away, turned by yaw around the y axis and then tilted by pitch
+#[derive(Debug, Clone)]
+pub struct Camera {
+    pub target: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+    // pixels per unit at the target
+    pub scale: f32,
+    pub projection: Projection,
+}
+
+impl Camera {
+    pub fn new(target: Vec3, distance: f32, scale: f32) -> Self {
+        Self {
+            target,
+            yaw: 0.,
+            pitch: 0.,
+            distance,
+            scale,
+            projection: Projection::Orthographic,
+        }
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-PI / 2., PI / 2.);
+    }
+
+    // Centers on the points and zooms so they fill size, from any angle
+    pub fn fit(&mut self, points: &[Vec3], size: Vec2) {
+        if points.is_empty() {
+            return;
+        }
+        let (min, max) = points.iter().fold((points[0], points[0]), |(min, max), p| (min.min(*p), max.max(*p)));
+        self.target = (min + max) * 0.5;
+        let radius = (max - min).length() * 0.5;
+        if radius > 0. {
+            self.scale = size.min_element() * 0.5 / radius;
+            self.distance = radius * 3.;
+        }
+    }
+
+    // in camera space, x right, y up and z towards the camera
+    pub fn to_view(&self, p: Vec3) -> Vec3 {
+        let p = p - self.target;
+        let (sy, cy) = self.yaw.sin_cos();
+        let p = vec3(p.x * cy + p.z * sy, p.y, -p.x * sy + p.z * cy);
+        let (sp, cp) = self.pitch.sin_cos();
+        vec3(p.x, p.y * cp - p.z * sp, p.y * sp + p.z * cp)
+    }
+
+    // Screen position and depth, the larger the depth the closer it is.
+    // Nothing for points behind a perspective camera.
+    pub fn project(&self, p: Vec3) -> Option<(Vec2, f32)> {
+        let v = self.to_view(p);
+        match self.projection {
+            Projection::Orthographic => Some((v.truncate() * self.scale, v.z)),
+            Projection::Perspective => {
+                let z = self.distance - v.z;
+                if z <= 1e-3 {
+                    return None;
+                }
+                Some((v.truncate() * self.scale * self.distance / z, v.z))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projections() {
+        let mut camera = Camera::new(vec3(1., 1., 1.), 10., 2.);
+        assert_eq!(camera.project(vec3(1., 1., 1.)).unwrap().0, Vec2::ZERO);
+        assert_eq!(camera.project(vec3(2., 1., 1.)).unwrap().0, vec2(2., 0.));
+
+        // turned a quarter, x goes away from the camera
+        camera.rotate(PI / 2., 0.);
+        let (p, depth) = camera.project(vec3(2., 1., 1.)).unwrap();
+        assert!(p.length() < 1e-5);
+        assert!((depth + 1.).abs() < 1e-5);
+
+        // same size at the target, bigger when closer
+        camera.rotate(-PI / 2., 0.);
+        camera.set_projection(Projection::Perspective);
+        assert!((camera.project(vec3(2., 1., 1.)).unwrap().0 - vec2(2., 0.)).length() < 1e-5);
+        assert!(camera.project(vec3(2., 1., 6.)).unwrap().0.x > 2.);
+        assert!(camera.project(vec3(1., 1., 20.)).is_none());
+    }
+}
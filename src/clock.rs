@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+// Fixed timestep clock, so a simulation runs at the same speed whatever the frame rate.
+// Feed it the real time of every update and run the simulation as many steps as it returns:
+//
+//     for _ in 0..model.clock.advance(update.since_last) {
+//         step(model);
+//     }
+//
+// alpha() tells how far we are between the last step and the next one, for
+// interpolating positions when rendering.
+#[derive(Debug, Clone)]
+pub struct SimClock {
+    dt: f32,
+    accumulator: f32,
+    time_scale: f32,
+    paused: bool,
+    pending_steps: usize,
+    max_steps: usize,
+    steps: u64,
+}
+
+impl SimClock {
+    pub fn new(steps_per_second: f32) -> Self {
+        Self {
+            dt: 1. / steps_per_second,
+            accumulator: 0.,
+            time_scale: 1.,
+            paused: false,
+            pending_steps: 0,
+            max_steps: 8,
+            steps: 0,
+        }
+    }
+
+    // Returns how many steps to run for this much real time. When the frame took
+    // too long only max_steps are run and the rest is dropped, otherwise a slow
+    // simulation keeps falling further behind.
+    pub fn advance(&mut self, elapsed: Duration) -> usize {
+        if self.paused {
+            let n = self.pending_steps;
+            self.pending_steps = 0;
+            self.steps += n as u64;
+            return n;
+        }
+        self.accumulator += elapsed.as_secs_f32() * self.time_scale;
+        let mut n = (self.accumulator / self.dt).floor() as usize;
+        self.accumulator -= n as f32 * self.dt;
+        if n > self.max_steps {
+            n = self.max_steps;
+            self.accumulator = 0.;
+        }
+        self.steps += n as u64;
+        n
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.dt
+    }
+
+    // Simulated seconds so far
+    pub fn time(&self) -> f32 {
+        self.steps as f32 * self.dt
+    }
+
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.accumulator = 0.;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.set_paused(!self.paused);
+    }
+
+    // Runs exactly one step on the next advance, only while paused
+    pub fn single_step(&mut self) {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.max(0.);
+    }
+
+    pub fn set_max_steps(&mut self, max_steps: usize) {
+        self.max_steps = max_steps;
+    }
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        SimClock::new(60.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_are_independent_of_frame_rate() {
+        let mut fast = SimClock::new(60.);
+        let mut slow = SimClock::new(60.);
+        let fast_steps: usize = (0..120).map(|_| fast.advance(Duration::from_secs_f32(1. / 120.))).sum();
+        let slow_steps: usize = (0..30).map(|_| slow.advance(Duration::from_secs_f32(1. / 30.))).sum();
+        assert!((fast_steps as i32 - 60).abs() <= 1);
+        assert!((slow_steps as i32 - 60).abs() <= 1);
+    }
+
+    #[test]
+    fn pause_and_single_step() {
+        let mut clock = SimClock::new(8.);
+        clock.set_time_scale(2.);
+        assert_eq!(clock.advance(Duration::from_millis(250)), 4);
+        assert!((clock.alpha() - 0.0).abs() < 1e-4);
+        clock.toggle_pause();
+        assert_eq!(clock.advance(Duration::from_secs(1)), 0);
+        clock.single_step();
+        assert_eq!(clock.advance(Duration::from_secs(1)), 1);
+        assert_eq!(clock.steps(), 5);
+        // a huge hitch only runs max_steps
+        clock.toggle_pause();
+        assert_eq!(clock.advance(Duration::from_secs(10)), 8);
+    }
+}
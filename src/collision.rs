@@ -0,0 +1,168 @@
+use nannou::prelude::*;
+use crate::geometry::{closest_point_on_polygon, closest_point_on_rect, closest_point_on_segment, point_in_polygon};
+use crate::particle::Particle;
+
+// Where and how deep a circle overlaps a shape.
+// The normal points from the shape towards the circle center, so moving the
+// circle by normal * penetration separates the two.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    pub point: Vec2,
+    pub normal: Vec2,
+    pub penetration: f32,
+}
+
+// What happens to a particle once it hit something
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Response {
+    // reflect the velocity, 1 keeps all the energy, 0 kills the normal part
+    Bounce { restitution: f32 },
+    // keep moving along the surface
+    Slide,
+    // stay on the surface
+    Stick,
+    // the shape swallows the particle, the caller decides what that means
+    Absorb,
+}
+
+pub fn circle_circle(center: Vec2, r: f32, other: Vec2, other_r: f32) -> Option<Contact> {
+    let d = center - other;
+    let distance = d.length();
+    if distance >= r + other_r {
+        return None;
+    }
+    // same center, any direction will do
+    let normal = if distance > 0. { d / distance } else { vec2(0., 1.) };
+    Some(Contact {
+        point: other + normal * other_r,
+        normal,
+        penetration: r + other_r - distance,
+    })
+}
+
+pub fn circle_segment(center: Vec2, r: f32, a: Vec2, b: Vec2) -> Option<Contact> {
+    let point = closest_point_on_segment(center, a, b);
+    let d = center - point;
+    let distance = d.length();
+    if distance >= r {
+        return None;
+    }
+    let normal = if distance > 0. { d / distance } else { (b - a).perp().normalize_or_zero() };
+    Some(Contact { point, normal, penetration: r - distance })
+}
+
+pub fn circle_rect(center: Vec2, r: f32, rect: Rect) -> Option<Contact> {
+    let point = closest_point_on_rect(center, rect);
+    if point != center {
+        let d = center - point;
+        let distance = d.length();
+        if distance >= r {
+            return None;
+        }
+        return Some(Contact { point, normal: d / distance, penetration: r - distance });
+    }
+
+    // the center is inside, push out through the closest edge
+    let edges = [
+        (center.x - rect.left(), vec2(-1., 0.)),
+        (rect.right() - center.x, vec2(1., 0.)),
+        (center.y - rect.bottom(), vec2(0., -1.)),
+        (rect.top() - center.y, vec2(0., 1.)),
+    ];
+    let (distance, normal) = edges.iter()
+        .fold(edges[0], |best, e| if e.0 < best.0 { *e } else { best });
+    Some(Contact {
+        point: center + normal * distance,
+        normal,
+        penetration: r + distance,
+    })
+}
+
+// Works for any simple polygon, the points go around the outline without repeating the first one
+pub fn circle_polygon(center: Vec2, r: f32, points: &[Vec2]) -> Option<Contact> {
+    if points.len() < 3 {
+        return None;
+    }
+    let (closest, ii) = closest_point_on_polygon(center, points)?;
+    let edge = (points[ii], points[(ii + 1) % points.len()]);
+    let distance = closest.distance(center);
+    let inside = point_in_polygon(center, points);
+    if !inside && distance >= r {
+        return None;
+    }
+    let mut normal = if distance > 0. {
+        (center - closest) / distance
+    } else {
+        (edge.1 - edge.0).perp().normalize_or_zero()
+    };
+    if inside {
+        normal = -normal;
+        // center sits right on the edge, make sure we push outwards
+        if distance == 0. && point_in_polygon(center + normal, points) {
+            normal = -normal;
+        }
+    }
+    let penetration = if inside { r + distance } else { r - distance };
+    Some(Contact { point: closest, normal, penetration })
+}
+
+// Moves the particle out of the shape and changes its velocity according to the response.
+// Returns false when the particle was absorbed.
+pub fn resolve(particle: &mut Particle, contact: &Contact, response: Response) -> bool {
+    if response == Response::Absorb {
+        return false;
+    }
+    particle.set_location(particle.location() + contact.normal * contact.penetration);
+    let v = particle.velocity();
+    let vn = v.dot(contact.normal);
+    let v = match response {
+        // only change the velocity if it is still moving into the shape
+        Response::Bounce { restitution } if vn < 0. => v - contact.normal * vn * (1. + restitution),
+        Response::Slide if vn < 0. => v - contact.normal * vn,
+        Response::Stick => Vec2::ZERO,
+        _ => v,
+    };
+    particle.set_velocity(v);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circle_rect_contacts() {
+        let rect = Rect::from_x_y_w_h(0., 0., 100., 20.);
+        let c = circle_rect(vec2(0., 13.), 5., rect).unwrap();
+        assert_eq!(c.normal, vec2(0., 1.));
+        assert!((c.penetration - 2.).abs() < 1e-5);
+        assert!(circle_rect(vec2(0., 16.), 5., rect).is_none());
+
+        // from the inside it leaves through the closest edge
+        let c = circle_rect(vec2(48., 0.), 5., rect).unwrap();
+        assert_eq!(c.normal, vec2(1., 0.));
+        assert!((c.penetration - 7.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn circle_polygon_contacts() {
+        let tri = [vec2(-10., 0.), vec2(10., 0.), vec2(0., 10.)];
+        let c = circle_polygon(vec2(0., -2.), 3., &tri).unwrap();
+        assert_eq!(c.normal, vec2(0., -1.));
+        let c = circle_polygon(vec2(0., 1.), 3., &tri).unwrap();
+        assert_eq!(c.normal, vec2(0., -1.));
+        assert!((c.penetration - 4.).abs() < 1e-5);
+        assert!(circle_polygon(vec2(0., -4.), 3., &tri).is_none());
+    }
+
+    #[test]
+    fn bounce_reflects_velocity() {
+        let mut p = Particle::new(vec2(0., 13.), srgba(0, 0, 0, 255), vec2(1., -4.), |_, _, _| {});
+        let rect = Rect::from_x_y_w_h(0., 0., 100., 20.);
+        let c = circle_rect(p.location(), 5., rect).unwrap();
+        assert!(resolve(&mut p, &c, Response::Bounce { restitution: 0.5 }));
+        assert_eq!(p.velocity(), vec2(1., 2.));
+        assert!(circle_rect(p.location(), 5., rect).is_none());
+        assert!(!resolve(&mut p, &c, Response::Absorb));
+    }
+}
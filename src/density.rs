@@ -0,0 +1,222 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use nannou::image::{ImageResult, Rgba, RgbaImage};
+use nannou::prelude::*;
+use rayon::prelude::*;
+use crate::attractor::Attractor;
+
+// How many times an orbit passed through every pixel of bounds
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    pub width: usize,
+    pub height: usize,
+    pub bounds: Rect,
+    counts: Vec<u32>,
+}
+
+impl Histogram {
+    pub fn new(width: usize, height: usize, bounds: Rect) -> Self {
+        Self { width, height, bounds, counts: vec![0; width * height] }
+    }
+
+    // row by row from the top, like an image
+    fn index(&self, p: Vec2) -> Option<usize> {
+        let x = ((p.x - self.bounds.left()) / self.bounds.w() * self.width as f32).floor();
+        let y = ((self.bounds.top() - p.y) / self.bounds.h() * self.height as f32).floor();
+        if x < 0. || y < 0. || x >= self.width as f32 || y >= self.height as f32 {
+            None
+        } else {
+            Some(y as usize * self.width + x as usize)
+        }
+    }
+
+    pub fn add(&mut self, p: Vec2) {
+        if let Some(ii) = self.index(p) {
+            self.counts[ii] = self.counts[ii].saturating_add(1);
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u32 {
+        self.counts[y * self.width + x]
+    }
+
+    pub fn max(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|c| *c as u64).sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.iter_mut().for_each(|c| *c = 0);
+    }
+
+    pub fn merge(&mut self, other: &Histogram) {
+        for (a, b) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *a = a.saturating_add(*b);
+        }
+    }
+
+    // Runs one orbit per thread, each from a slightly different start, and adds them all up.
+    // The first few points of every orbit are skipped, they are not on the attractor yet.
+    // All the threads count into the same atomic bins, so there is only one extra buffer.
+    pub fn accumulate<A: Attractor + Sync + ?Sized>(&mut self, attractor: &A, iterations: usize) {
+        let threads = rayon::current_num_threads().max(1);
+        let per_thread = iterations / threads;
+        let bins: Vec<AtomicU32> = (0..self.counts.len()).map(|_| AtomicU32::new(0)).collect();
+        let this = &*self;
+        (0..threads).into_par_iter().for_each(|ii| {
+            // the last one also runs what doesn't divide evenly
+            let count = if ii + 1 == threads { iterations - per_thread * (threads - 1) } else { per_thread };
+            let mut p = attractor.start() + vec2(random_range(-0.01, 0.01), random_range(-0.01, 0.01));
+            for _ in 0..100 {
+                p = attractor.step(p);
+            }
+            for _ in 0..count {
+                p = attractor.step(p);
+                if let Some(jj) = this.index(p) {
+                    bins[jj].fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+        for (c, b) in self.counts.iter_mut().zip(bins) {
+            *c = c.saturating_add(b.into_inner());
+        }
+    }
+}
+
+// Turns counts into brightness. The counts go from a few to millions, so they are
+// compressed with a log first, gamma then brings out the faint parts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMap {
+    pub log: bool,
+    pub gamma: f32,
+    // scales the result before clamping, above 1 burns out the densest parts
+    pub exposure: f32,
+}
+
+impl Default for ToneMap {
+    fn default() -> Self {
+        Self { log: true, gamma: 2.2, exposure: 1. }
+    }
+}
+
+impl ToneMap {
+    // 0..1
+    pub fn apply(&self, count: u32, max: u32) -> f32 {
+        if count == 0 || max == 0 {
+            return 0.;
+        }
+        let v = if self.log {
+            (count as f32).ln_1p() / (max as f32).ln_1p()
+        } else {
+            count as f32 / max as f32
+        };
+        (v.powf(1. / self.gamma) * self.exposure).min(1.)
+    }
+}
+
+// Colors evenly spread from 0 to 1, blended in between
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub colors: Vec<Srgb<u8>>,
+}
+
+impl Gradient {
+    pub fn new(colors: Vec<Srgb<u8>>) -> Self {
+        Self { colors }
+    }
+
+    // from one of our palettes
+    pub fn from_palette(palette: &[(u8, u8, u8)]) -> Self {
+        Gradient::new(palette.iter().map(|(r, g, b)| srgb(*r, *g, *b)).collect())
+    }
+
+    pub fn at(&self, t: f32) -> Srgb<u8> {
+        match self.colors.len() {
+            0 => srgb(0, 0, 0),
+            1 => self.colors[0],
+            n => {
+                let t = t.clamp(0., 1.) * (n - 1) as f32;
+                let ii = (t.floor() as usize).min(n - 2);
+                let f = t - ii as f32;
+                let (a, b) = (self.colors[ii], self.colors[ii + 1]);
+                let l = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * f).round() as u8;
+                srgb(l(a.red, b.red), l(a.green, b.green), l(a.blue, b.blue))
+            }
+        }
+    }
+}
+
+fn luminance(c: &Srgb<u8>) -> f32 {
+    0.2126 * c.red as f32 + 0.7152 * c.green as f32 + 0.0722 * c.blue as f32
+}
+
+// black through the night palette from dark to light, to white, so the busiest parts are the brightest
+impl Default for Gradient {
+    fn default() -> Self {
+        let mut night: Vec<Srgb<u8>> = crate::NIGHT_PALETTE.iter().map(|(r, g, b)| srgb(*r, *g, *b)).collect();
+        night.sort_by(|a, b| luminance(a).total_cmp(&luminance(b)));
+        let mut colors = vec![srgb(0, 0, 0)];
+        colors.extend(night);
+        colors.push(srgb(255, 255, 255));
+        Gradient::new(colors)
+    }
+}
+
+pub fn render(histogram: &Histogram, tone: &ToneMap, gradient: &Gradient) -> RgbaImage {
+    let max = histogram.max();
+    RgbaImage::from_fn(histogram.width as u32, histogram.height as u32, |x, y| {
+        let c = gradient.at(tone.apply(histogram.get(x as usize, y as usize), max));
+        Rgba([c.red, c.green, c.blue, 255])
+    })
+}
+
+pub fn save_png<P: AsRef<Path>>(histogram: &Histogram, tone: &ToneMap, gradient: &Gradient, path: P) -> ImageResult<()> {
+    render(histogram, tone, gradient).save(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attractor::Clifford;
+
+    #[test]
+    fn histogram_counts_points() {
+        let mut h = Histogram::new(4, 2, Rect::from_w_h(4., 2.));
+        h.add(vec2(-1.5, 0.5));
+        h.add(vec2(-1.5, 0.5));
+        h.add(vec2(1.5, -0.5));
+        h.add(vec2(10., 0.));
+        assert_eq!(h.get(0, 0), 2);
+        assert_eq!(h.get(3, 1), 1);
+        assert_eq!(h.max(), 2);
+        assert_eq!(h.total(), 3);
+
+        let attractor = Clifford::default();
+        let mut h = Histogram::new(64, 64, attractor.bounds(10000).pad(-0.1));
+        h.accumulate(&attractor, 100000);
+        assert!(h.total() > 90000);
+        // every iteration is counted, even when they don't split evenly between the threads
+        let mut h = Histogram::new(8, 8, Rect::from_w_h(100., 100.));
+        h.accumulate(&attractor, 10007);
+        assert_eq!(h.total(), 10007);
+    }
+
+    #[test]
+    fn tone_and_gradient_ends() {
+        let tone = ToneMap::default();
+        assert_eq!(tone.apply(0, 100), 0.);
+        assert!((tone.apply(100, 100) - 1.).abs() < 1e-6);
+        assert!(tone.apply(10, 100) > 10. / 100.);
+        let g = Gradient::new(vec![srgb(0, 0, 0), srgb(200, 100, 50)]);
+        assert_eq!(g.at(0.), srgb(0, 0, 0));
+        assert_eq!(g.at(0.5), srgb(100, 50, 25));
+        assert_eq!(g.at(2.), srgb(200, 100, 50));
+        // the default only gets brighter
+        let g = Gradient::default();
+        assert!(g.colors.windows(2).all(|w| luminance(&w[0]) <= luminance(&w[1])));
+        assert_eq!(g.at(1.), srgb(255, 255, 255));
+    }
+}
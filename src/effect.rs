@@ -0,0 +1,217 @@
+use nannou::prelude::*;
+use crate::particle::Particle;
+
+// Shapes the progress of an effect, t goes from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    QuadOut,
+    CubicOut,
+    ExpoOut,
+    SineInOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadOut => 1. - (1. - t).powi(2),
+            Easing::CubicOut => 1. - (1. - t).powi(3),
+            Easing::ExpoOut => if t >= 1. { 1. } else { 1. - 2f32.powf(-10. * t) },
+            Easing::SineInOut => -((PI * t).cos() - 1.) / 2.,
+        }
+    }
+}
+
+fn debris_draw_fn(draw: &Draw, location: Vec2, color: Srgba<u8>) {
+    draw.ellipse().xy(location).radius(1.).color(color);
+}
+
+// A ring that grows and fades out, optionally throwing some debris around
+pub struct Effect {
+    pub position: Vec2,
+    pub color: Srgba<u8>,
+    pub start_radius: f32,
+    pub end_radius: f32,
+    // 0 draws a filled circle
+    pub thickness: f32,
+    // in seconds
+    pub lifetime: f32,
+    pub easing: Easing,
+    age: f32,
+    debris: Vec<Particle>,
+}
+
+impl Effect {
+    pub fn new(position: Vec2, color: Srgba<u8>, lifetime: f32) -> Self {
+        Self {
+            position,
+            color,
+            start_radius: 2.,
+            end_radius: 10.,
+            thickness: 1.,
+            lifetime,
+            easing: Easing::CubicOut,
+            age: 0.,
+            debris: vec![],
+        }
+    }
+
+    // the small ring a drop leaves behind
+    pub fn ripple(position: Vec2, color: Srgba<u8>) -> Self {
+        let mut effect = Effect::new(position, color, 0.35);
+        effect.set_radius(2., 6.);
+        effect
+    }
+
+    // a filled flash with debris flying out of it
+    pub fn burst(position: Vec2, color: Srgba<u8>, debris: usize, speed: f32) -> Self {
+        let mut effect = Effect::new(position, color, 0.5);
+        effect.set_radius(1., 8.);
+        effect.set_thickness(0.);
+        effect.set_easing(Easing::ExpoOut);
+        effect.spawn_debris(debris, speed);
+        effect
+    }
+
+    pub fn set_radius(&mut self, start: f32, end: f32) {
+        self.start_radius = start;
+        self.end_radius = end;
+    }
+
+    pub fn set_thickness(&mut self, thickness: f32) {
+        self.thickness = thickness;
+    }
+
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.easing = easing;
+    }
+
+    // debris leaves upwards, speed in pixels per step
+    pub fn spawn_debris(&mut self, count: usize, speed: f32) {
+        for _ in 0..count {
+            let angle = random_range(0.1, PI - 0.1);
+            let v = vec2(angle.cos(), angle.sin()) * speed * random_range(0.5, 1.);
+            self.debris.push(Particle::new(self.position, self.color, v, debris_draw_fn));
+        }
+    }
+
+    // 0..1, how far along the effect is
+    pub fn progress(&self) -> f32 {
+        (self.age / self.lifetime).min(1.)
+    }
+
+    pub fn radius(&self) -> f32 {
+        let t = self.easing.apply(self.progress());
+        self.start_radius + (self.end_radius - self.start_radius) * t
+    }
+
+    pub fn alpha(&self) -> u8 {
+        let t = self.easing.apply(self.progress());
+        (self.color.alpha as f32 * (1. - t)) as u8
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+
+    // dt in seconds, gravity only pulls the debris
+    pub fn update(&mut self, dt: f32, gravity: f32) {
+        self.age += dt;
+        let alpha = self.alpha();
+        for d in self.debris.iter_mut() {
+            d.apply_force(vec2(0., -gravity));
+            d.update();
+            let mut c = d.color();
+            c.alpha = alpha;
+            d.set_color(c);
+        }
+    }
+
+    pub fn display(&self, draw: &Draw) {
+        let mut c = self.color;
+        c.alpha = self.alpha();
+        if self.thickness > 0. {
+            draw.ellipse()
+                .xy(self.position)
+                .radius(self.radius())
+                .no_fill()
+                .stroke_weight(self.thickness)
+                .stroke(c);
+        } else {
+            draw.ellipse().xy(self.position).radius(self.radius()).color(c);
+        }
+        for d in self.debris.iter() {
+            d.display(draw);
+        }
+    }
+}
+
+// All the running effects, dead ones are dropped on update
+pub struct Effects {
+    pub gravity: f32,
+    effects: Vec<Effect>,
+}
+
+impl Effects {
+    pub fn new(gravity: f32) -> Self {
+        Self { gravity, effects: vec![] }
+    }
+
+    pub fn push(&mut self, effect: Effect) {
+        self.effects.push(effect);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let gravity = self.gravity;
+        for effect in self.effects.iter_mut() {
+            effect.update(dt, gravity);
+        }
+        self.effects.retain(|e| e.is_alive());
+    }
+
+    pub fn display(&self, draw: &Draw) {
+        for effect in self.effects.iter() {
+            effect.display(draw);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easing_endpoints() {
+        for e in [Easing::Linear, Easing::QuadOut, Easing::CubicOut, Easing::ExpoOut, Easing::SineInOut].iter() {
+            assert!(e.apply(0.).abs() < 1e-5);
+            assert!((e.apply(1.) - 1.).abs() < 1e-5);
+            assert!(e.apply(0.5) > 0. && e.apply(0.5) < 1.);
+        }
+    }
+
+    #[test]
+    fn effects_expire() {
+        let mut effects = Effects::new(0.1);
+        let mut e = Effect::new(Vec2::ZERO, srgba(255, 255, 255, 200), 1.);
+        e.set_easing(Easing::Linear);
+        effects.push(e);
+        effects.push(Effect::burst(Vec2::ZERO, srgba(255, 255, 255, 200), 5, 2.));
+        effects.update(0.5);
+        // the burst only lives half a second
+        assert_eq!(effects.len(), 1);
+        assert_eq!(effects.effects[0].alpha(), 100);
+        assert!((effects.effects[0].radius() - 6.).abs() < 1e-5);
+        effects.update(0.5);
+        assert!(effects.is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+use nannou::prelude::*;
+use crate::particle::Particle;
+use crate::spatial::{SpatialHash, SpatialIndex};
+
+// Steering behaviours after Craig Reynolds, every behaviour returns a steering force,
+// that is the desired velocity minus the current velocity, limited to max_force
+
+fn steer(particle: &Particle, desired: Vec2, max_force: f32) -> Vec2 {
+    (desired - particle.velocity()).clamp_length_max(max_force)
+}
+
+pub fn seek(particle: &Particle, target: Vec2, max_speed: f32, max_force: f32) -> Vec2 {
+    let desired = (target - particle.location()).normalize_or_zero() * max_speed;
+    steer(particle, desired, max_force)
+}
+
+pub fn flee(particle: &Particle, target: Vec2, max_speed: f32, max_force: f32) -> Vec2 {
+    let desired = (particle.location() - target).normalize_or_zero() * max_speed;
+    steer(particle, desired, max_force)
+}
+
+// Like seek, but slows down inside the slowing radius and stops on the target
+pub fn arrive(particle: &Particle, target: Vec2, slowing_radius: f32, max_speed: f32, max_force: f32) -> Vec2 {
+    let offset = target - particle.location();
+    let distance = offset.length();
+    let speed = if distance < slowing_radius {
+        map_range(distance, 0., slowing_radius, 0., max_speed)
+    } else {
+        max_speed
+    };
+    steer(particle, offset.normalize_or_zero() * speed, max_force)
+}
+
+// Steer away from neighbours closer than radius, closer ones push harder
+pub fn separation(particle: &Particle, neighbors: &[&Particle], radius: f32, max_speed: f32, max_force: f32) -> Vec2 {
+    let mut sum = Vec2::ZERO;
+    let mut count = 0;
+    for other in neighbors {
+        let offset = particle.location() - other.location();
+        let d = offset.length();
+        if d > 0. && d < radius {
+            sum += offset.normalize() / d;
+            count += 1;
+        }
+    }
+    if count == 0 {
+        return Vec2::ZERO;
+    }
+    steer(particle, sum.normalize_or_zero() * max_speed, max_force)
+}
+
+// Steer towards the average heading of the neighbours
+pub fn alignment(particle: &Particle, neighbors: &[&Particle], max_speed: f32, max_force: f32) -> Vec2 {
+    if neighbors.is_empty() {
+        return Vec2::ZERO;
+    }
+    let sum: Vec2 = neighbors.iter().fold(Vec2::ZERO, |acc, p| acc + p.velocity());
+    steer(particle, sum.normalize_or_zero() * max_speed, max_force)
+}
+
+// Steer towards the center of the neighbours
+pub fn cohesion(particle: &Particle, neighbors: &[&Particle], max_speed: f32, max_force: f32) -> Vec2 {
+    if neighbors.is_empty() {
+        return Vec2::ZERO;
+    }
+    let sum: Vec2 = neighbors.iter().fold(Vec2::ZERO, |acc, p| acc + p.location());
+    seek(particle, sum / neighbors.len() as f32, max_speed, max_force)
+}
+
+// Random but smooth wandering, a target moves on a circle projected in front of the particle
+#[derive(Debug, Clone)]
+pub struct Wander {
+    pub distance: f32,
+    pub radius: f32,
+    pub jitter: f32,
+    angle: f32,
+}
+
+impl Wander {
+    pub fn new(distance: f32, radius: f32, jitter: f32) -> Self {
+        Self { distance, radius, jitter, angle: random_range(0., TAU) }
+    }
+
+    pub fn steer(&mut self, particle: &Particle, max_speed: f32, max_force: f32) -> Vec2 {
+        self.angle += random_range(-self.jitter, self.jitter);
+        let heading = particle.velocity().normalize_or_zero();
+        let center = particle.location() + heading * self.distance;
+        let target = center + vec2(self.angle.cos(), self.angle.sin()) * self.radius;
+        seek(particle, target, max_speed, max_force)
+    }
+}
+
+impl Default for Wander {
+    fn default() -> Self {
+        Wander::new(40., 20., 0.3)
+    }
+}
+
+// Where the whole flock wants to go, on top of the flocking rules
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Goal {
+    None,
+    Seek(Vec2),
+    Flee(Vec2),
+    Arrive(Vec2),
+}
+
+#[derive(Debug, Clone)]
+pub struct FlockParams {
+    pub neighbor_radius: f32,
+    pub separation_radius: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+    pub wander: f32,
+    pub goal: f32,
+    pub slowing_radius: f32,
+    pub max_speed: f32,
+    pub max_force: f32,
+}
+
+impl Default for FlockParams {
+    fn default() -> Self {
+        Self {
+            neighbor_radius: 50.,
+            separation_radius: 25.,
+            separation: 1.5,
+            alignment: 1.,
+            cohesion: 1.,
+            wander: 0.3,
+            goal: 1.,
+            slowing_radius: 100.,
+            max_speed: 3.,
+            max_force: 0.05,
+        }
+    }
+}
+
+pub struct Flock {
+    pub params: FlockParams,
+    pub goal: Goal,
+    wanders: Vec<Wander>,
+    index: SpatialHash,
+}
+
+impl Flock {
+    pub fn new(params: FlockParams) -> Self {
+        let index = SpatialHash::new(params.neighbor_radius);
+        Self {
+            params,
+            goal: Goal::None,
+            wanders: vec![],
+            index,
+        }
+    }
+
+    // Computes the steering of every particle from its neighbours, then moves them
+    pub fn update(&mut self, particles: &mut [Particle]) {
+        let params = &self.params;
+        self.wanders.resize_with(particles.len(), Wander::default);
+        if self.index.cell_size() != params.neighbor_radius {
+            self.index = SpatialHash::new(params.neighbor_radius);
+        }
+        for (ii, particle) in particles.iter().enumerate() {
+            self.index.update(ii, particle.location());
+        }
+        for ii in particles.len()..self.index.len() {
+            self.index.remove(ii);
+        }
+
+        let mut forces = Vec::with_capacity(particles.len());
+        for (ii, particle) in particles.iter().enumerate() {
+            let neighbors: Vec<&Particle> = self.index
+                .query_radius(particle.location(), params.neighbor_radius)
+                .into_iter()
+                .filter(|jj| *jj != ii)
+                .map(|jj| &particles[jj])
+                .collect();
+            let (speed, force) = (params.max_speed, params.max_force);
+            let mut f = separation(particle, &neighbors, params.separation_radius, speed, force) * params.separation
+                + alignment(particle, &neighbors, speed, force) * params.alignment
+                + cohesion(particle, &neighbors, speed, force) * params.cohesion
+                + self.wanders[ii].steer(particle, speed, force) * params.wander;
+            f += match self.goal {
+                Goal::None => Vec2::ZERO,
+                Goal::Seek(target) => seek(particle, target, speed, force),
+                Goal::Flee(target) => flee(particle, target, speed, force),
+                Goal::Arrive(target) => arrive(particle, target, params.slowing_radius, speed, force),
+            } * params.goal;
+            forces.push(f);
+        }
+
+        for (particle, force) in particles.iter_mut().zip(forces) {
+            particle.apply_force(force);
+            particle.limit_speed(params.max_speed);
+            particle.update();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn particle(location: Vec2, velocity: Vec2) -> Particle {
+        Particle::new(location, srgba(0, 0, 0, 255), velocity, |_, _, _| {})
+    }
+
+    #[test]
+    fn arrive_stops_on_target() {
+        let p = particle(vec2(10., 0.), Vec2::ZERO);
+        assert_eq!(arrive(&p, vec2(10., 0.), 50., 3., 1.), Vec2::ZERO);
+        let p = particle(Vec2::ZERO, Vec2::ZERO);
+        assert!(seek(&p, vec2(10., 0.), 3., 1.).x > 0.);
+        assert!(flee(&p, vec2(10., 0.), 3., 1.).x < 0.);
+    }
+
+    #[test]
+    fn separation_pushes_away() {
+        let p = particle(Vec2::ZERO, Vec2::ZERO);
+        let other = particle(vec2(5., 0.), Vec2::ZERO);
+        let f = separation(&p, &[&other], 25., 3., 0.5);
+        assert!(f.x < 0.);
+        assert!(f.length() <= 0.5 + f32::EPSILON);
+    }
+}
@@ -0,0 +1,290 @@
+use nannou::noise::NoiseFn;
+use nannou::prelude::*;
+use rayon::prelude::*;
+
+// Anything that gives a direction at a position and time
+pub trait FlowField {
+    fn sample(&self, position: Vec2, time: f32) -> Vec2;
+}
+
+// so a plain closure can be used as a field
+impl<F> FlowField for F
+    where F: Fn(Vec2, f32) -> Vec2 {
+    fn sample(&self, position: Vec2, time: f32) -> Vec2 {
+        self(position, time)
+    }
+}
+
+// Layers of noise, every octave has a higher frequency and a lower amplitude
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Octaves {
+    pub count: usize,
+    pub lacunarity: f64,
+    pub persistence: f64,
+}
+
+impl Default for Octaves {
+    fn default() -> Self {
+        Self { count: 1, lacunarity: 2., persistence: 0.5 }
+    }
+}
+
+impl Octaves {
+    pub fn new(count: usize, lacunarity: f64, persistence: f64) -> Self {
+        Self { count, lacunarity, persistence }
+    }
+}
+
+// fractal brownian motion, stays around -1..1 no matter how many octaves
+pub fn fbm_2d<N: NoiseFn<[f64; 2]>>(noise: &N, x: f64, y: f64, octaves: &Octaves) -> f64 {
+    let (mut sum, mut norm, mut amp, mut freq) = (0., 0., 1., 1.);
+    for _ in 0..octaves.count.max(1) {
+        sum += noise.get([x * freq, y * freq]) * amp;
+        norm += amp;
+        amp *= octaves.persistence;
+        freq *= octaves.lacunarity;
+    }
+    sum / norm
+}
+
+// z is usually the time, the pattern changes smoothly as it moves
+pub fn fbm_3d<N: NoiseFn<[f64; 3]>>(noise: &N, x: f64, y: f64, z: f64, octaves: &Octaves) -> f64 {
+    let (mut sum, mut norm, mut amp, mut freq) = (0., 0., 1., 1.);
+    for _ in 0..octaves.count.max(1) {
+        sum += noise.get([x * freq, y * freq, z * freq]) * amp;
+        norm += amp;
+        amp *= octaves.persistence;
+        freq *= octaves.lacunarity;
+    }
+    sum / norm
+}
+
+// central difference of any scalar field
+pub fn gradient<F: Fn(f64, f64) -> f64>(f: F, x: f64, y: f64, eps: f64) -> Vec2 {
+    let a = (f(x + eps, y) - f(x - eps, y)) / (2. * eps);
+    let b = (f(x, y + eps) - f(x, y - eps)) / (2. * eps);
+    vec2(a as f32, b as f32)
+}
+
+// The curl of a scalar field is its gradient turned by 90 degrees,
+// it has no sinks or sources so things following it never bunch up
+pub fn curl<F: Fn(f64, f64) -> f64>(f: F, x: f64, y: f64, eps: f64) -> Vec2 {
+    let g = gradient(f, x, y, eps);
+    vec2(g.y, -g.x)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlowMode {
+    // swirls, never converges
+    Curl,
+    // flows up hill, collects on the peaks
+    Gradient,
+}
+
+// A flow field made out of noise.
+// Positions are multiplied by scale before sampling the noise, time by time_scale,
+// a time_scale of 0 keeps it to 2D noise.
+pub struct NoiseFlow<N> {
+    pub noise: N,
+    pub mode: FlowMode,
+    pub octaves: Octaves,
+    pub scale: f64,
+    pub time_scale: f64,
+    pub eps: f64,
+    pub strength: f32,
+}
+
+impl<N> NoiseFlow<N>
+    where N: NoiseFn<[f64; 2]> + NoiseFn<[f64; 3]> {
+    pub fn new(noise: N, mode: FlowMode) -> Self {
+        Self {
+            noise,
+            mode,
+            octaves: Octaves::default(),
+            scale: 1.,
+            time_scale: 0.,
+            eps: 0.01,
+            strength: 1.,
+        }
+    }
+
+    pub fn set_octaves(&mut self, octaves: Octaves) {
+        self.octaves = octaves;
+    }
+
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = scale;
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale;
+    }
+
+    pub fn set_eps(&mut self, eps: f64) {
+        self.eps = eps;
+    }
+
+    pub fn set_strength(&mut self, strength: f32) {
+        self.strength = strength;
+    }
+
+    // the scalar noise value the flow is derived from
+    pub fn potential(&self, x: f64, y: f64, time: f32) -> f64 {
+        if self.time_scale == 0. {
+            fbm_2d(&self.noise, x, y, &self.octaves)
+        } else {
+            fbm_3d(&self.noise, x, y, time as f64 * self.time_scale, &self.octaves)
+        }
+    }
+}
+
+impl<N> FlowField for NoiseFlow<N>
+    where N: NoiseFn<[f64; 2]> + NoiseFn<[f64; 3]> {
+    fn sample(&self, position: Vec2, time: f32) -> Vec2 {
+        let x = position.x as f64 * self.scale;
+        let y = position.y as f64 * self.scale;
+        let f = |x, y| self.potential(x, y, time);
+        let v = match self.mode {
+            FlowMode::Curl => curl(f, x, y, self.eps),
+            FlowMode::Gradient => gradient(f, x, y, self.eps),
+        };
+        v * self.strength
+    }
+}
+
+// A field sampled once on a grid covering rect, looking it up afterwards is
+// just a bilinear interpolation of the four nearest samples
+pub struct FlowGrid {
+    pub rect: Rect,
+    pub cell_size: f32,
+    cols: usize,
+    rows: usize,
+    vectors: Vec<Vec2>,
+}
+
+impl FlowGrid {
+    pub fn new<F: FlowField + ?Sized>(field: &F, rect: Rect, cell_size: f32, time: f32) -> Self {
+        let cols = (rect.w() / cell_size).ceil() as usize + 1;
+        let rows = (rect.h() / cell_size).ceil() as usize + 1;
+        let mut grid = Self { rect, cell_size, cols, rows, vectors: vec![] };
+        grid.rebuild(field, time);
+        grid
+    }
+
+    // samples the field again, for fields that change over time
+    pub fn rebuild<F: FlowField + ?Sized>(&mut self, field: &F, time: f32) {
+        self.vectors.clear();
+        for jj in 0..self.rows {
+            for ii in 0..self.cols {
+                self.vectors.push(field.sample(self.node(ii, jj), time));
+            }
+        }
+    }
+
+    pub fn node(&self, ii: usize, jj: usize) -> Vec2 {
+        vec2(self.rect.left(), self.rect.bottom()) + vec2(ii as f32, jj as f32) * self.cell_size
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    // the stored sample at a grid node
+    pub fn get(&self, ii: usize, jj: usize) -> Vec2 {
+        self.vectors[jj * self.cols + ii]
+    }
+
+    // positions outside the rect get the value at the closest edge
+    pub fn at(&self, position: Vec2) -> Vec2 {
+        let gx = ((position.x - self.rect.left()) / self.cell_size).max(0.).min((self.cols - 1) as f32);
+        let gy = ((position.y - self.rect.bottom()) / self.cell_size).max(0.).min((self.rows - 1) as f32);
+        let (ii, jj) = (gx.floor() as usize, gy.floor() as usize);
+        let (ni, nj) = ((ii + 1).min(self.cols - 1), (jj + 1).min(self.rows - 1));
+        let (tx, ty) = (gx - ii as f32, gy - jj as f32);
+        let bottom = self.get(ii, jj).lerp(self.get(ni, jj), tx);
+        let top = self.get(ii, nj).lerp(self.get(ni, nj), tx);
+        bottom.lerp(top, ty)
+    }
+}
+
+impl FlowField for FlowGrid {
+    fn sample(&self, position: Vec2, _time: f32) -> Vec2 {
+        self.at(position)
+    }
+}
+
+// Line integral convolution, smears white noise along the field so the flow shows up as a texture.
+// Returns w * h brightness values 0..1 for the pixels of rect, row by row from the top.
+// length is how many steps of one pixel are taken each way.
+pub fn lic<F: FlowField + Sync + ?Sized>(field: &F, rect: Rect, w: usize, h: usize, length: usize, time: f32) -> Vec<f32> {
+    let noise: Vec<f32> = (0..w * h).map(|_| random::<f32>()).collect();
+    let pixel = vec2(rect.w() / w as f32, rect.h() / h as f32);
+    let to_pixel = |p: Vec2| {
+        let x = ((p.x - rect.left()) / pixel.x).floor();
+        let y = ((rect.top() - p.y) / pixel.y).floor();
+        if x < 0. || y < 0. || x >= w as f32 || y >= h as f32 {
+            None
+        } else {
+            Some(y as usize * w + x as usize)
+        }
+    };
+    (0..w * h).into_par_iter().map(|ii| {
+        let start = vec2(
+            rect.left() + ((ii % w) as f32 + 0.5) * pixel.x,
+            rect.top() - ((ii / w) as f32 + 0.5) * pixel.y,
+        );
+        let mut sum = noise[ii];
+        let mut count = 1.;
+        for dir in [1., -1.].iter() {
+            let mut p = start;
+            for _ in 0..length {
+                let v = field.sample(p, time).normalize_or_zero();
+                if v == Vec2::ZERO {
+                    break;
+                }
+                p += v * pixel.max_element() * *dir;
+                match to_pixel(p) {
+                    Some(jj) => {
+                        sum += noise[jj];
+                        count += 1.;
+                    }
+                    None => break,
+                }
+            }
+        }
+        sum / count
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise_source::{NoiseKind, NoiseSource};
+
+    #[test]
+    fn curl_is_perpendicular_to_gradient() {
+        let flow = NoiseFlow::new(NoiseSource::new(NoiseKind::Perlin), FlowMode::Curl);
+        let grad = NoiseFlow::new(NoiseSource::new(NoiseKind::Perlin), FlowMode::Gradient);
+        for p in [vec2(0.3, 0.7), vec2(-2.1, 5.4), vec2(10.2, -3.3)].iter() {
+            let c = flow.sample(*p, 0.);
+            let g = grad.sample(*p, 0.);
+            assert!(c.dot(g).abs() < 1e-4);
+            assert!((c.length() - g.length()).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn grid_interpolates_linear_fields() {
+        let field = |p: Vec2, _t: f32| vec2(p.x * 2. + 1., p.y - p.x);
+        let grid = FlowGrid::new(&field, Rect::from_w_h(100., 100.), 10., 0.);
+        for p in [vec2(3., 7.), vec2(-41.5, 12.25), vec2(49., -49.)].iter() {
+            assert!(grid.at(*p).distance(field(*p, 0.)) < 1e-3);
+        }
+        // clamped to the edge outside
+        assert!(grid.at(vec2(80., 0.)).distance(field(vec2(50., 0.), 0.)) < 1e-3);
+    }
+}
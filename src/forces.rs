@@ -0,0 +1,290 @@
+use nannou::prelude::*;
+use rayon::prelude::*;
+use crate::particle::Particle;
+use crate::spatial::{SpatialHash, SpatialIndex};
+
+// Pairwise forces between particles. The functions here only change the velocities,
+// call Particle::update afterwards to move them.
+
+// Acceleration of a body at p towards a mass at other, the softening keeps
+// close encounters from blowing up
+pub fn gravity(p: Vec2, other: Vec2, mass: f32, g: f32, softening: f32) -> Vec2 {
+    let d = other - p;
+    let r2 = d.length_squared() + softening * softening;
+    d * (g * mass / (r2 * r2.sqrt()))
+}
+
+// Lennard-Jones like force pushing a away from b, the distance is clamped so
+// overlapping particles get a strong but finite push, and the attractive part
+// is dropped so it only ever repels
+pub fn soft_repulsion(a: Vec2, b: Vec2, sigma: f32, strength: f32, max_force: f32) -> Vec2 {
+    let d = a - b;
+    let r = d.length().max(sigma * 0.5);
+    if r >= sigma * 2.5 || d == Vec2::ZERO {
+        return Vec2::ZERO;
+    }
+    let s6 = (sigma / r).powi(6);
+    let magnitude = 24. * strength / r * (2. * s6 * s6 - s6);
+    (d.normalize() * magnitude.max(0.)).clamp_length_max(max_force)
+}
+
+// Direct O(n^2) gravity, fine for a few hundred particles
+pub fn apply_gravity(particles: &mut [Particle], masses: &[f32], g: f32, softening: f32) {
+    let positions: Vec<Vec2> = particles.iter().map(|p| p.location()).collect();
+    let forces: Vec<Vec2> = (0..positions.len()).into_par_iter().map(|ii| {
+        let mut f = Vec2::ZERO;
+        for (jj, other) in positions.iter().enumerate() {
+            if ii != jj {
+                f += gravity(positions[ii], *other, masses[jj], g, softening);
+            }
+        }
+        f
+    }).collect();
+    for (particle, force) in particles.iter_mut().zip(forces) {
+        particle.apply_force(force);
+    }
+}
+
+// Gravity using a Barnes-Hut tree, far away groups are treated as one body.
+// theta around 0.5 is the usual trade off, 0 is the same as the direct sum
+pub fn apply_gravity_barnes_hut(particles: &mut [Particle], masses: &[f32], g: f32, softening: f32, theta: f32) {
+    let positions: Vec<Vec2> = particles.iter().map(|p| p.location()).collect();
+    let tree = BarnesHut::new(&positions, masses);
+    let forces: Vec<Vec2> = (0..positions.len()).into_par_iter()
+        .map(|ii| tree.acceleration(positions[ii], Some(ii), theta, g, softening))
+        .collect();
+    for (particle, force) in particles.iter_mut().zip(forces) {
+        particle.apply_force(force);
+    }
+}
+
+// Short range repulsion so particles don't stack up, only neighbours within 2.5 sigma are checked
+pub fn apply_repulsion(particles: &mut [Particle], sigma: f32, strength: f32, max_force: f32) {
+    let positions: Vec<Vec2> = particles.iter().map(|p| p.location()).collect();
+    let mut index = SpatialHash::new(sigma * 2.5);
+    for (ii, p) in positions.iter().enumerate() {
+        index.insert(ii, *p);
+    }
+    let forces: Vec<Vec2> = (0..positions.len()).into_par_iter().map(|ii| {
+        index.query_radius(positions[ii], sigma * 2.5)
+            .into_iter()
+            .filter(|jj| *jj != ii)
+            .fold(Vec2::ZERO, |f, jj| f + soft_repulsion(positions[ii], positions[jj], sigma, strength, max_force))
+    }).collect();
+    for (particle, force) in particles.iter_mut().zip(forces) {
+        particle.apply_force(force);
+    }
+}
+
+// "Particle life": every particle has a class, and the matrix says how much class a
+// is attracted (positive) or repelled (negative) by class b. The rules don't have to
+// be symmetric, which is what makes the little creatures chase each other.
+pub struct ParticleLife {
+    pub matrix: Vec<Vec<f32>>,
+    pub radius: f32,
+    // below this fraction of the radius everything repels
+    pub beta: f32,
+    pub strength: f32,
+    pub friction: f32,
+}
+
+impl ParticleLife {
+    pub fn new(classes: usize, radius: f32) -> Self {
+        let mut life = Self {
+            matrix: vec![vec![0.; classes]; classes],
+            radius,
+            beta: 0.3,
+            strength: 1.,
+            friction: 0.1,
+        };
+        life.randomize();
+        life
+    }
+
+    pub fn randomize(&mut self) {
+        for row in self.matrix.iter_mut() {
+            for v in row.iter_mut() {
+                *v = random_range(-1., 1.);
+            }
+        }
+    }
+
+    pub fn classes(&self) -> usize {
+        self.matrix.len()
+    }
+
+    // force for a normalised distance r in 0..1 and an attraction value
+    pub fn force(&self, r: f32, attraction: f32) -> f32 {
+        if r < self.beta {
+            r / self.beta - 1.
+        } else if r < 1. {
+            attraction * (1. - (2. * r - 1. - self.beta).abs() / (1. - self.beta))
+        } else {
+            0.
+        }
+    }
+
+    pub fn apply(&self, particles: &mut [Particle], classes: &[usize]) {
+        let positions: Vec<Vec2> = particles.iter().map(|p| p.location()).collect();
+        let mut index = SpatialHash::new(self.radius);
+        for (ii, p) in positions.iter().enumerate() {
+            index.insert(ii, *p);
+        }
+        let forces: Vec<Vec2> = (0..positions.len()).into_par_iter().map(|ii| {
+            let mut f = Vec2::ZERO;
+            for jj in index.query_radius(positions[ii], self.radius) {
+                let d = positions[jj] - positions[ii];
+                let r = d.length();
+                if jj == ii || r == 0. {
+                    continue;
+                }
+                let a = self.matrix[classes[ii]][classes[jj]];
+                f += d / r * self.force(r / self.radius, a);
+            }
+            f * self.strength
+        }).collect();
+        for (particle, force) in particles.iter_mut().zip(forces) {
+            particle.set_velocity(particle.velocity() * (1. - self.friction));
+            particle.apply_force(force);
+        }
+    }
+}
+
+// Deeper than this, bodies that landed in the same cell are merged into one
+const MAX_DEPTH: usize = 24;
+
+struct BhNode {
+    center: Vec2,
+    half: f32,
+    mass: f32,
+    // weighted sum while building, center of mass afterwards
+    com: Vec2,
+    count: usize,
+    body: Option<usize>,
+    children: Option<[usize; 4]>,
+}
+
+impl BhNode {
+    fn new(center: Vec2, half: f32) -> Self {
+        Self { center, half, mass: 0., com: Vec2::ZERO, count: 0, body: None, children: None }
+    }
+}
+
+pub struct BarnesHut {
+    nodes: Vec<BhNode>,
+}
+
+impl BarnesHut {
+    pub fn new(positions: &[Vec2], masses: &[f32]) -> Self {
+        let (mut min, mut max) = (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN));
+        for p in positions {
+            min = min.min(*p);
+            max = max.max(*p);
+        }
+        let center = if positions.is_empty() { Vec2::ZERO } else { (min + max) * 0.5 };
+        let half = ((max - min).max_element() * 0.5).max(1.);
+        let mut tree = Self { nodes: vec![BhNode::new(center, half)] };
+        for ii in 0..positions.len() {
+            tree.insert(0, ii, positions, masses, 0);
+        }
+        for node in tree.nodes.iter_mut() {
+            if node.mass > 0. {
+                node.com /= node.mass;
+            }
+        }
+        tree
+    }
+
+    fn child_for(&self, node: usize, p: Vec2) -> usize {
+        let n = &self.nodes[node];
+        let ix = if p.x < n.center.x { 0 } else { 1 };
+        let iy = if p.y < n.center.y { 0 } else { 2 };
+        n.children.unwrap()[ix + iy]
+    }
+
+    fn subdivide(&mut self, node: usize) {
+        let (center, half) = (self.nodes[node].center, self.nodes[node].half * 0.5);
+        let mut children = [0; 4];
+        for (ii, offset) in [vec2(-1., -1.), vec2(1., -1.), vec2(-1., 1.), vec2(1., 1.)].iter().enumerate() {
+            children[ii] = self.nodes.len();
+            self.nodes.push(BhNode::new(center + *offset * half, half));
+        }
+        self.nodes[node].children = Some(children);
+    }
+
+    fn insert(&mut self, node: usize, body: usize, positions: &[Vec2], masses: &[f32], depth: usize) {
+        let p = positions[body];
+        let n = &mut self.nodes[node];
+        n.mass += masses[body];
+        n.com += p * masses[body];
+        n.count += 1;
+        if n.count == 1 {
+            n.body = Some(body);
+            return;
+        }
+        if depth >= MAX_DEPTH {
+            return;
+        }
+        if n.children.is_none() {
+            self.subdivide(node);
+            if let Some(old) = self.nodes[node].body.take() {
+                let child = self.child_for(node, positions[old]);
+                self.insert(child, old, positions, masses, depth + 1);
+            }
+        }
+        let child = self.child_for(node, p);
+        self.insert(child, body, positions, masses, depth + 1);
+    }
+
+    // Acceleration at p, skip is the body at p itself so it doesn't attract itself
+    pub fn acceleration(&self, p: Vec2, skip: Option<usize>, theta: f32, g: f32, softening: f32) -> Vec2 {
+        let mut f = Vec2::ZERO;
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let n = &self.nodes[node];
+            if n.count == 0 {
+                continue;
+            }
+            match n.children {
+                Some(children) if n.half * 2. >= theta * p.distance(n.com) => {
+                    stack.extend_from_slice(&children);
+                }
+                _ => {
+                    if n.count == 1 && n.body == skip {
+                        continue;
+                    }
+                    f += gravity(p, n.com, n.mass, g, softening);
+                }
+            }
+        }
+        f
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn barnes_hut_matches_direct_sum() {
+        let positions: Vec<Vec2> = (0..200)
+            .map(|ii| vec2((ii as f32 * 7.3).sin() * 300., (ii as f32 * 3.1).cos() * 200.))
+            .collect();
+        let masses = vec![1.; positions.len()];
+        let tree = BarnesHut::new(&positions, &masses);
+        let p = vec2(500., 400.);
+        let direct = positions.iter().fold(Vec2::ZERO, |f, o| f + gravity(p, *o, 1., 1., 1.));
+        let exact = tree.acceleration(p, None, 0., 1., 1.);
+        let approx = tree.acceleration(p, None, 0.5, 1., 1.);
+        assert!((exact - direct).length() < direct.length() * 1e-4);
+        assert!((approx - direct).length() < direct.length() * 0.05);
+    }
+
+    #[test]
+    fn particle_life_repels_up_close() {
+        let life = ParticleLife::new(2, 50.);
+        assert!(life.force(0.1, 1.) < 0.);
+        assert_eq!(life.force(1.5, 1.), 0.);
+        assert!(soft_repulsion(vec2(1., 0.), Vec2::ZERO, 2., 1., 10.).x > 0.);
+    }
+}
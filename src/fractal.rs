@@ -0,0 +1,255 @@
+use std::path::Path;
+use nannou::image::{ImageResult, RgbaImage};
+use rayon::prelude::*;
+use crate::density::Gradient;
+
+// Escape time fractals, everything in f64 so zooming in goes a long way before it gets blocky
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FractalKind {
+    Mandelbrot,
+    // z² + c with a fixed c and the pixel as the start
+    Julia,
+    // the mandelbrot with the absolute values taken before squaring
+    BurningShip,
+    // newton's method on z³ - 1, colored by which root it ends up at
+    Newton,
+}
+
+impl FractalKind {
+    pub fn next(self) -> Self {
+        match self {
+            FractalKind::Mandelbrot => FractalKind::Julia,
+            FractalKind::Julia => FractalKind::BurningShip,
+            FractalKind::BurningShip => FractalKind::Newton,
+            FractalKind::Newton => FractalKind::Mandelbrot,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FractalKind::Mandelbrot => "mandelbrot",
+            FractalKind::Julia => "julia",
+            FractalKind::BurningShip => "burning ship",
+            FractalKind::Newton => "newton",
+        }
+    }
+}
+
+// What happened to a point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Escape {
+    // never left, it is in the set
+    Inside,
+    // left after a smooth, fractional number of iterations
+    Escaped(f64),
+    // settled on a root, after this many iterations
+    Root(usize, usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Fractal {
+    pub kind: FractalKind,
+    pub max_iterations: usize,
+    // the c of the julia set
+    pub julia: (f64, f64),
+}
+
+// the roots of z³ - 1
+const ROOTS: [(f64, f64); 3] = [(1., 0.), (-0.5, 0.866_025_403_784_438_6), (-0.5, -0.866_025_403_784_438_6)];
+
+impl Fractal {
+    pub fn new(kind: FractalKind) -> Self {
+        Self { kind, max_iterations: 256, julia: (-0.8, 0.156) }
+    }
+
+    pub fn set_kind(&mut self, kind: FractalKind) {
+        self.kind = kind;
+    }
+
+    pub fn set_max_iterations(&mut self, max_iterations: usize) {
+        self.max_iterations = max_iterations.max(1);
+    }
+
+    pub fn set_julia(&mut self, re: f64, im: f64) {
+        self.julia = (re, im);
+    }
+
+    pub fn escape(&self, re: f64, im: f64) -> Escape {
+        match self.kind {
+            FractalKind::Mandelbrot => self.escape_time(0., 0., re, im, false),
+            FractalKind::Julia => self.escape_time(re, im, self.julia.0, self.julia.1, false),
+            // flipped so the ship sits upright with y going up
+            FractalKind::BurningShip => self.escape_time(0., 0., re, -im, true),
+            FractalKind::Newton => self.newton(re, im),
+        }
+    }
+
+    fn escape_time(&self, mut x: f64, mut y: f64, cx: f64, cy: f64, burning: bool) -> Escape {
+        // a large radius makes the smoothing below accurate
+        let radius = 256. * 256.;
+        for ii in 0..self.max_iterations {
+            if burning {
+                x = x.abs();
+                y = y.abs();
+            }
+            let (xx, yy) = (x * x, y * y);
+            if xx + yy > radius {
+                // takes the fraction of the last step into account, so there are no bands
+                let smooth = ii as f64 + 1. - ((xx + yy).ln() / 2.).ln() / std::f64::consts::LN_2;
+                return Escape::Escaped(smooth.max(0.));
+            }
+            y = 2. * x * y + cy;
+            x = xx - yy + cx;
+        }
+        Escape::Inside
+    }
+
+    fn newton(&self, mut x: f64, mut y: f64) -> Escape {
+        for ii in 0..self.max_iterations {
+            for (r, (rx, ry)) in ROOTS.iter().enumerate() {
+                if (x - rx).powi(2) + (y - ry).powi(2) < 1e-12 {
+                    return Escape::Root(r, ii);
+                }
+            }
+            // z - (z³ - 1) / 3z²
+            let (x2, y2) = (x * x - y * y, 2. * x * y);
+            let (x3, y3) = (x2 * x - y2 * y, x2 * y + y2 * x);
+            let (dx, dy) = (3. * x2, 3. * y2);
+            let d = dx * dx + dy * dy;
+            if d == 0. {
+                return Escape::Inside;
+            }
+            let (nx, ny) = (x3 - 1., y3);
+            x -= (nx * dx + ny * dy) / d;
+            y -= (ny * dx - nx * dy) / d;
+        }
+        Escape::Inside
+    }
+}
+
+// The part of the complex plane on screen, width is in plane units and the height follows the image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub center: (f64, f64),
+    pub width: f64,
+}
+
+impl Viewport {
+    pub fn new(center: (f64, f64), width: f64) -> Self {
+        Self { center, width }
+    }
+
+    pub fn for_kind(kind: FractalKind) -> Self {
+        match kind {
+            FractalKind::Mandelbrot => Viewport::new((-0.6, 0.), 3.5),
+            FractalKind::Julia => Viewport::new((0., 0.), 3.5),
+            FractalKind::BurningShip => Viewport::new((-0.4, 0.5), 3.5),
+            FractalKind::Newton => Viewport::new((0., 0.), 4.),
+        }
+    }
+
+    // pixel x right, y down, from the top left corner, to the plane with y up
+    pub fn to_plane(&self, px: f64, py: f64, w: usize, h: usize) -> (f64, f64) {
+        let scale = self.width / w as f64;
+        (
+            self.center.0 + (px - w as f64 / 2.) * scale,
+            self.center.1 - (py - h as f64 / 2.) * scale,
+        )
+    }
+
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.center.0 += dx;
+        self.center.1 += dy;
+    }
+
+    // Zooms by factor, below 1 goes in, keeping the point at (x, y) where it is
+    pub fn zoom_at(&mut self, x: f64, y: f64, factor: f64) {
+        self.center.0 = x + (self.center.0 - x) * factor;
+        self.center.1 = y + (self.center.1 - y) * factor;
+        self.width *= factor;
+    }
+}
+
+// Turns an escape into a color. Escape counts go around the gradient every period iterations,
+// the roots get a color each and darken the longer they took.
+#[derive(Debug, Clone)]
+pub struct Coloring {
+    pub gradient: Gradient,
+    pub period: f64,
+    pub inside: [u8; 3],
+}
+
+impl Coloring {
+    pub fn new(gradient: Gradient) -> Self {
+        Self { gradient, period: 64., inside: [0, 0, 0] }
+    }
+
+    pub fn color(&self, escape: Escape, max_iterations: usize) -> [u8; 3] {
+        match escape {
+            Escape::Inside => self.inside,
+            Escape::Escaped(n) => {
+                // there and back again so going around has no seam
+                let t = (n / self.period).fract();
+                let c = self.gradient.at(1. - (2. * t as f32 - 1.).abs());
+                [c.red, c.green, c.blue]
+            }
+            Escape::Root(root, n) => {
+                let c = self.gradient.at((root as f32 + 0.5) / ROOTS.len() as f32);
+                let shade = 1. - (n as f32 / max_iterations.min(64) as f32).min(1.) * 0.8;
+                let s = |v: u8| (v as f32 * shade) as u8;
+                [s(c.red), s(c.green), s(c.blue)]
+            }
+        }
+    }
+}
+
+// One row per task, the rows take very different times so rayon's work stealing helps
+pub fn render(fractal: &Fractal, viewport: &Viewport, coloring: &Coloring, w: usize, h: usize) -> RgbaImage {
+    let mut pixels = vec![0u8; w * h * 4];
+    pixels.par_chunks_mut(w * 4).enumerate().for_each(|(y, row)| {
+        for (x, pixel) in row.chunks_mut(4).enumerate() {
+            let (re, im) = viewport.to_plane(x as f64 + 0.5, y as f64 + 0.5, w, h);
+            let [r, g, b] = coloring.color(fractal.escape(re, im), fractal.max_iterations);
+            pixel.copy_from_slice(&[r, g, b, 255]);
+        }
+    });
+    RgbaImage::from_raw(w as u32, h as u32, pixels).unwrap()
+}
+
+pub fn save_png<P: AsRef<Path>>(fractal: &Fractal, viewport: &Viewport, coloring: &Coloring, w: usize, h: usize, path: P) -> ImageResult<()> {
+    render(fractal, viewport, coloring, w, h).save(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_points() {
+        let mandelbrot = Fractal::new(FractalKind::Mandelbrot);
+        assert_eq!(mandelbrot.escape(0., 0.), Escape::Inside);
+        assert_eq!(mandelbrot.escape(-1., 0.), Escape::Inside);
+        match mandelbrot.escape(2., 2.) {
+            Escape::Escaped(n) => assert!(n < 5.),
+            e => panic!("{:?}", e),
+        }
+
+        let newton = Fractal::new(FractalKind::Newton);
+        assert!(matches!(newton.escape(2., 0.1), Escape::Root(0, _)));
+        assert!(matches!(newton.escape(-1., 2.), Escape::Root(1, _)));
+        assert!(matches!(newton.escape(-1., -2.), Escape::Root(2, _)));
+    }
+
+    #[test]
+    fn viewport_zoom_keeps_the_point() {
+        let mut view = Viewport::new((0., 0.), 4.);
+        assert_eq!(view.to_plane(50., 25., 100, 50), (0., 0.));
+        assert_eq!(view.to_plane(0., 0., 100, 50), (-2., 1.));
+        view.zoom_at(1., 1., 0.5);
+        assert_eq!(view.width, 2.);
+        assert_eq!(view.center, (0.5, 0.5));
+
+        let image = render(&Fractal::new(FractalKind::Mandelbrot), &view, &Coloring::new(Gradient::default()), 8, 4);
+        assert_eq!(image.dimensions(), (8, 4));
+    }
+}
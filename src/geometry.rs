@@ -0,0 +1,166 @@
+use nannou::prelude::*;
+
+// Intersection and containment tests for the simple shapes the sketches use.
+// Rects follow nannou's y-up coordinates, so top() is bigger than bottom().
+// Polygons are a list of points going around the outline, the last point
+// connects back to the first one.
+
+pub fn closest_point_on_segment(p: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let len = ab.length_squared();
+    if len == 0. {
+        return a;
+    }
+    a + ab * ((p - a).dot(ab) / len).clamp(0., 1.)
+}
+
+pub fn closest_point_on_rect(p: Vec2, rect: Rect) -> Vec2 {
+    vec2(
+        p.x.max(rect.left()).min(rect.right()),
+        p.y.max(rect.bottom()).min(rect.top()),
+    )
+}
+
+// Closest point on the outline, with the index of the edge it is on, None without any points
+pub fn closest_point_on_polygon(p: Vec2, points: &[Vec2]) -> Option<(Vec2, usize)> {
+    let mut closest = (*points.first()?, 0);
+    let mut best = f32::MAX;
+    for ii in 0..points.len() {
+        let c = closest_point_on_segment(p, points[ii], points[(ii + 1) % points.len()]);
+        let d = c.distance_squared(p);
+        if d < best {
+            best = d;
+            closest = (c, ii);
+        }
+    }
+    Some(closest)
+}
+
+pub fn point_in_rect(p: Vec2, rect: Rect) -> bool {
+    p.x >= rect.left() && p.x <= rect.right() && p.y >= rect.bottom() && p.y <= rect.top()
+}
+
+pub fn point_in_circle(p: Vec2, center: Vec2, r: f32) -> bool {
+    p.distance_squared(center) <= r * r
+}
+
+// Even-odd rule, works for concave polygons too
+pub fn point_in_polygon(p: Vec2, points: &[Vec2]) -> bool {
+    if points.is_empty() {
+        return false;
+    }
+    let mut inside = false;
+    let mut j = points.len() - 1;
+    for i in 0..points.len() {
+        let (a, b) = (points[i], points[j]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+pub fn circle_circle_intersects(a: Vec2, ra: f32, b: Vec2, rb: f32) -> bool {
+    a.distance_squared(b) <= (ra + rb) * (ra + rb)
+}
+
+pub fn circle_rect_intersects(center: Vec2, r: f32, rect: Rect) -> bool {
+    point_in_circle(closest_point_on_rect(center, rect), center, r)
+}
+
+pub fn circle_segment_intersects(center: Vec2, r: f32, a: Vec2, b: Vec2) -> bool {
+    point_in_circle(closest_point_on_segment(center, a, b), center, r)
+}
+
+pub fn circle_polygon_intersects(center: Vec2, r: f32, points: &[Vec2]) -> bool {
+    match closest_point_on_polygon(center, points) {
+        Some((closest, _)) => point_in_polygon(center, points) || point_in_circle(closest, center, r),
+        None => false,
+    }
+}
+
+pub fn rect_rect_intersects(a: Rect, b: Rect) -> bool {
+    a.left() <= b.right() && a.right() >= b.left() && a.bottom() <= b.top() && a.top() >= b.bottom()
+}
+
+// Where the segments a1-a2 and b1-b2 cross, parallel segments never do
+pub fn segment_intersection(a1: Vec2, a2: Vec2, b1: Vec2, b2: Vec2) -> Option<Vec2> {
+    let r = a2 - a1;
+    let s = b2 - b1;
+    let denom = r.perp_dot(s);
+    if denom == 0. {
+        return None;
+    }
+    let t = (b1 - a1).perp_dot(s) / denom;
+    let u = (b1 - a1).perp_dot(r) / denom;
+    if (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u) {
+        Some(a1 + r * t)
+    } else {
+        None
+    }
+}
+
+pub fn rect_contains_circle(rect: Rect, center: Vec2, r: f32) -> bool {
+    center.x - r >= rect.left() && center.x + r <= rect.right()
+        && center.y - r >= rect.bottom() && center.y + r <= rect.top()
+}
+
+pub fn circle_contains_circle(outer: Vec2, outer_r: f32, inner: Vec2, inner_r: f32) -> bool {
+    inner_r <= outer_r && outer.distance(inner) + inner_r <= outer_r
+}
+
+pub fn polygon_contains_circle(points: &[Vec2], center: Vec2, r: f32) -> bool {
+    point_in_polygon(center, points)
+        && closest_point_on_polygon(center, points).is_some_and(|(closest, _)| closest.distance(center) >= r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circle_rect() {
+        let rect = Rect::from_x_y_w_h(0., 0., 100., 20.);
+        // inside, touching the top and just above it
+        assert!(circle_rect_intersects(vec2(0., 0.), 5., rect));
+        assert!(circle_rect_intersects(vec2(0., 14.), 5., rect));
+        assert!(!circle_rect_intersects(vec2(0., 16.), 5., rect));
+        // below the rect, which the y-down version got wrong
+        assert!(circle_rect_intersects(vec2(0., -14.), 5., rect));
+        assert!(!circle_rect_intersects(vec2(0., -16.), 5., rect));
+        // corners use the real distance, not the box around the circle
+        assert!(!circle_rect_intersects(vec2(54., 14.), 5., rect));
+        assert!(circle_rect_intersects(vec2(53., 13.), 5., rect));
+        assert!(rect_contains_circle(rect, vec2(0., 0.), 5.));
+        assert!(!rect_contains_circle(rect, vec2(0., 6.), 5.));
+    }
+
+    #[test]
+    fn segments() {
+        let p = segment_intersection(vec2(-1., 0.), vec2(1., 0.), vec2(0., -1.), vec2(0., 1.));
+        assert_eq!(p, Some(vec2(0., 0.)));
+        assert_eq!(segment_intersection(vec2(-1., 0.), vec2(1., 0.), vec2(2., -1.), vec2(2., 1.)), None);
+        assert_eq!(segment_intersection(vec2(0., 0.), vec2(1., 0.), vec2(0., 1.), vec2(1., 1.)), None);
+        assert!(circle_segment_intersects(vec2(0., 2.), 2., vec2(-5., 0.), vec2(5., 0.)));
+        assert!(!circle_segment_intersects(vec2(7., 2.), 2., vec2(-5., 0.), vec2(5., 0.)));
+    }
+
+    #[test]
+    fn polygons() {
+        // a concave "L"
+        let l = [vec2(0., 0.), vec2(20., 0.), vec2(20., 10.), vec2(10., 10.), vec2(10., 20.), vec2(0., 20.)];
+        assert!(point_in_polygon(vec2(5., 15.), &l));
+        assert!(!point_in_polygon(vec2(15., 15.), &l));
+        assert!(circle_polygon_intersects(vec2(15., 15.), 6., &l));
+        assert!(!circle_polygon_intersects(vec2(16., 16.), 5., &l));
+        assert!(polygon_contains_circle(&l, vec2(5., 5.), 4.));
+        assert!(!polygon_contains_circle(&l, vec2(5., 5.), 6.));
+        // nothing is in or near an empty polygon
+        assert!(!point_in_polygon(Vec2::ZERO, &[]));
+        assert!(!circle_polygon_intersects(Vec2::ZERO, 5., &[]));
+        assert!(!polygon_contains_circle(&[], Vec2::ZERO, 5.));
+        assert!(circle_contains_circle(Vec2::ZERO, 10., vec2(3., 0.), 5.));
+        assert!(circle_circle_intersects(Vec2::ZERO, 1., vec2(2., 0.), 1.));
+    }
+}
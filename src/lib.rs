@@ -1,9 +1,34 @@
+// noise 0.7 glob exports two structs called Perlin, naming it warns however it is imported.
+// Only noise_source needs to, the rest goes through NoiseSource. The lint can only be allowed here.
+#![allow(ambiguous_glob_imports)]
 use nannou::color::IntoLinSrgba;
 use nannou::draw::properties::ColorScalar;
 use nannou::prelude::*;
 
 pub mod particle;
 pub mod ca;
+pub mod spatial;
+pub mod flocking;
+pub mod forces;
+pub mod geometry;
+pub mod collision;
+pub mod lightning;
+pub mod weather;
+pub mod skyline;
+pub mod clock;
+pub mod effect;
+pub mod flow;
+pub mod streamline;
+pub mod noise_source;
+pub mod attractor;
+pub mod attractor_search;
+pub mod density;
+pub mod ode;
+pub mod camera;
+pub mod fractal;
+pub mod turtle;
+pub mod lsystem;
+pub mod tree;
 
 pub type C8 = Srgba<u8>;
 pub type CF32 = Srgba<f32>;
@@ -0,0 +1,185 @@
+use nannou::glam::Mat2;
+use nannou::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub thickness: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct LightningParams {
+    // how many times every segment is split in two
+    pub generations: usize,
+    // max sideways displacement of a midpoint, as a fraction of the whole bolt length.
+    // It halves every generation.
+    pub offset: f32,
+    // chance of a fork at each new midpoint
+    pub branch_chance: f32,
+    // fork length relative to the rest of the segment it splits from
+    pub branch_length: f32,
+    // max angle a fork leaves at, in radians
+    pub branch_angle: f32,
+    pub thickness: f32,
+    // forks are this much thinner than their parent
+    pub thickness_decay: f32,
+}
+
+impl Default for LightningParams {
+    fn default() -> Self {
+        Self {
+            generations: 7,
+            offset: 0.15,
+            branch_chance: 0.12,
+            branch_length: 0.7,
+            branch_angle: 0.6,
+            thickness: 3.,
+            thickness_decay: 0.6,
+        }
+    }
+}
+
+// A bolt made by midpoint displacement, with forks that get thinner the further they are from the trunk
+#[derive(Debug, Clone)]
+pub struct Bolt {
+    pub segments: Vec<Segment>,
+    // the trunk's, the forks fade relative to it
+    pub thickness: f32,
+}
+
+impl Bolt {
+    pub fn generate(start: Vec2, end: Vec2, params: &LightningParams) -> Self {
+        let mut segments = vec![Segment { start, end, thickness: params.thickness }];
+        let mut offset = start.distance(end) * params.offset;
+        for _ in 0..params.generations {
+            let mut next = Vec::with_capacity(segments.len() * 2);
+            for s in segments {
+                let dir = s.end - s.start;
+                let mid = (s.start + s.end) * 0.5 + dir.perp().normalize_or_zero() * random_range(-offset, offset);
+                next.push(Segment { start: s.start, end: mid, thickness: s.thickness });
+                next.push(Segment { start: mid, end: s.end, thickness: s.thickness });
+                if random::<f32>() < params.branch_chance {
+                    let angle = random_range(-params.branch_angle, params.branch_angle);
+                    let branch = Mat2::from_angle(angle) * (mid - s.start) * params.branch_length;
+                    next.push(Segment { start: mid, end: mid + branch, thickness: s.thickness * params.thickness_decay });
+                }
+            }
+            segments = next;
+            offset *= 0.5;
+        }
+        Self { segments, thickness: params.thickness }
+    }
+
+    // Draws the bolt in a few passes, wide and faint first then the bright core on top.
+    // intensity is 0..1, usually the flash value.
+    pub fn display(&self, draw: &Draw, color: Srgba<u8>, intensity: f32) {
+        let passes = [(6., 0.08), (3., 0.2), (1.5, 0.5), (0.6, 1.)];
+        for (width, alpha) in passes.iter() {
+            for s in self.segments.iter() {
+                let mut c = color;
+                // the core is whiter than the glow
+                if *alpha >= 1. {
+                    c = srgba(255, 255, 255, 255);
+                }
+                c.alpha = (255. * alpha * intensity * (s.thickness / self.thickness).min(1.)) as u8;
+                draw.line()
+                    .start(s.start)
+                    .end(s.end)
+                    .weight(s.thickness * width)
+                    .caps_round()
+                    .color(c);
+            }
+        }
+    }
+}
+
+// Brightness envelope of a strike, quick rise, a couple of flickers, then a slow fade
+#[derive(Debug, Clone)]
+pub struct Flash {
+    pub attack: f32,
+    pub decay: f32,
+    age: Option<f32>,
+}
+
+impl Flash {
+    pub fn new(attack: f32, decay: f32) -> Self {
+        Self { attack, decay, age: None }
+    }
+
+    pub fn trigger(&mut self) {
+        self.age = Some(0.);
+    }
+
+    // dt in seconds
+    pub fn update(&mut self, dt: f32) {
+        if let Some(age) = self.age {
+            let age = age + dt;
+            self.age = if age > self.attack + self.decay { None } else { Some(age) };
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.age.is_some()
+    }
+
+    // 0..1
+    pub fn value(&self) -> f32 {
+        match self.age {
+            None => 0.,
+            Some(age) if age < self.attack => age / self.attack,
+            Some(age) => {
+                let t = (age - self.attack) / self.decay;
+                let flicker = 0.7 + 0.3 * (t * 40.).cos();
+                (1. - t).max(0.).powi(2) * flicker
+            }
+        }
+    }
+}
+
+impl Default for Flash {
+    fn default() -> Self {
+        Flash::new(0.05, 0.8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bolt_stays_connected() {
+        let params = LightningParams { branch_chance: 0., thickness: 5., ..Default::default() };
+        let bolt = Bolt::generate(vec2(0., 100.), vec2(0., -100.), &params);
+        assert_eq!(bolt.segments.len(), 2usize.pow(params.generations as u32));
+        assert_eq!(bolt.thickness, 5.);
+        assert_eq!(bolt.segments[0].start, vec2(0., 100.));
+        assert_eq!(bolt.segments.last().unwrap().end, vec2(0., -100.));
+        for pair in bolt.segments.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+
+        // every fork is thinner than the trunk
+        let params = LightningParams { branch_chance: 1., generations: 3, ..Default::default() };
+        let bolt = Bolt::generate(vec2(0., 100.), vec2(0., -100.), &params);
+        assert!(bolt.segments.iter().any(|s| s.thickness < params.thickness));
+        assert!(bolt.segments.iter().all(|s| s.thickness <= params.thickness));
+    }
+
+    #[test]
+    fn flash_rises_and_fades() {
+        let mut flash = Flash::new(0.1, 1.);
+        assert!(!flash.is_active());
+        assert_eq!(flash.value(), 0.);
+        flash.trigger();
+        flash.update(0.05);
+        assert!((flash.value() - 0.5).abs() < 1e-5);
+        flash.update(0.05);
+        assert!(flash.value() > 0.9);
+        flash.update(0.8);
+        assert!(flash.value() < 0.1);
+        flash.update(0.3);
+        assert!(!flash.is_active());
+        assert_eq!(flash.value(), 0.);
+    }
+}
@@ -0,0 +1,317 @@
+use nannou::prelude::*;
+use crate::turtle::{Segment, Turtle};
+
+// One letter of an L-system string, with the numbers of a parametric system, F(10) or A(1, 0.5)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub symbol: char,
+    pub params: Vec<f32>,
+}
+
+impl Module {
+    pub fn new(symbol: char) -> Self {
+        Self { symbol, params: vec![] }
+    }
+
+    pub fn with(symbol: char, params: &[f32]) -> Self {
+        Self { symbol, params: params.to_vec() }
+    }
+
+    pub fn param(&self, ii: usize) -> Option<f32> {
+        self.params.get(ii).copied()
+    }
+}
+
+// "F+[A(1,0.5)]" to modules, spaces are skipped and numbers that don't parse are dropped
+pub fn parse(text: &str) -> Vec<Module> {
+    let mut modules = vec![];
+    let mut chars = text.chars().filter(|c| !c.is_whitespace()).peekable();
+    while let Some(symbol) = chars.next() {
+        let mut module = Module::new(symbol);
+        if chars.peek() == Some(&'(') {
+            chars.next();
+            let inside: String = chars.by_ref().take_while(|c| *c != ')').collect();
+            module.params = inside.split(',').filter_map(|v| v.parse().ok()).collect();
+        }
+        modules.push(module);
+    }
+    modules
+}
+
+pub fn to_string(modules: &[Module]) -> String {
+    modules.iter().map(|m| {
+        if m.params.is_empty() {
+            m.symbol.to_string()
+        } else {
+            let params: Vec<String> = m.params.iter().map(|p| p.to_string()).collect();
+            format!("{}({})", m.symbol, params.join(","))
+        }
+    }).collect()
+}
+
+type ProductionFn = Box<dyn Fn(&[f32]) -> Vec<Module> + Send + Sync>;
+type Condition = Box<dyn Fn(&[f32]) -> bool + Send + Sync>;
+
+pub enum Production {
+    Fixed(Vec<Module>),
+    // builds the replacement from the parameters of the module it replaces
+    Parametric(ProductionFn),
+}
+
+impl Production {
+    fn apply(&self, params: &[f32]) -> Vec<Module> {
+        match self {
+            Production::Fixed(modules) => modules.clone(),
+            Production::Parametric(f) => f(params),
+        }
+    }
+}
+
+// Replaces symbol, with one of the productions picked by weight.
+// With a condition it only applies to the modules whose parameters pass it.
+pub struct Rule {
+    pub symbol: char,
+    condition: Option<Condition>,
+    productions: Vec<(f32, Production)>,
+}
+
+impl Rule {
+    pub fn new(symbol: char, replacement: &str) -> Self {
+        Rule::stochastic(symbol, &[(1., replacement)])
+    }
+
+    pub fn stochastic(symbol: char, choices: &[(f32, &str)]) -> Self {
+        Self {
+            symbol,
+            condition: None,
+            productions: choices.iter().map(|(w, r)| (*w, Production::Fixed(parse(r)))).collect(),
+        }
+    }
+
+    pub fn parametric<F: Fn(&[f32]) -> Vec<Module> + Send + Sync + 'static>(symbol: char, f: F) -> Self {
+        Self {
+            symbol,
+            condition: None,
+            productions: vec![(1., Production::Parametric(Box::new(f)))],
+        }
+    }
+
+    pub fn when<F: Fn(&[f32]) -> bool + Send + Sync + 'static>(mut self, condition: F) -> Self {
+        self.condition = Some(Box::new(condition));
+        self
+    }
+
+    pub fn applies(&self, module: &Module) -> bool {
+        module.symbol == self.symbol && self.condition.as_ref().is_none_or(|c| c(&module.params))
+    }
+
+    fn produce(&self, params: &[f32]) -> Vec<Module> {
+        let total: f32 = self.productions.iter().map(|(w, _)| w).sum();
+        let mut pick = random::<f32>() * total;
+        for (w, production) in self.productions.iter() {
+            if pick < *w {
+                return production.apply(params);
+            }
+            pick -= w;
+        }
+        self.productions.last().map_or(vec![], |(_, p)| p.apply(params))
+    }
+}
+
+// The axiom, the rules and how the turtle reads the result:
+//   F, G  forward drawing a line, by the parameter if there is one or else by step
+//   f     forward without drawing
+//   + -   turn left or right by angle, or by the parameter, in degrees
+//   |     turn around
+//   [ ]   remember where the turtle is and go back there
+//   !     set the line width to the parameter, or scale it by width_scale
+// Anything else is only there for the rules.
+pub struct LSystem {
+    pub axiom: Vec<Module>,
+    pub rules: Vec<Rule>,
+    // degrees, like the books use
+    pub angle: f32,
+    pub step: f32,
+    pub width: f32,
+    pub width_scale: f32,
+    pub color: Srgba<u8>,
+    // how many times to apply the rules for a good looking result
+    pub iterations: usize,
+}
+
+impl LSystem {
+    pub fn new(axiom: &str, angle: f32, iterations: usize) -> Self {
+        Self {
+            axiom: parse(axiom),
+            rules: vec![],
+            angle,
+            step: 10.,
+            width: 1.,
+            width_scale: 0.7,
+            color: srgba(0, 0, 0, 255),
+            iterations,
+        }
+    }
+
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    pub fn set_step(&mut self, step: f32) {
+        self.step = step;
+    }
+
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width;
+    }
+
+    pub fn set_color(&mut self, color: Srgba<u8>) {
+        self.color = color;
+    }
+
+    // One generation, every module is replaced at the same time, the first rule that applies wins
+    pub fn rewrite(&self, modules: &[Module]) -> Vec<Module> {
+        let mut next = Vec::with_capacity(modules.len() * 2);
+        for module in modules.iter() {
+            match self.rules.iter().find(|r| r.applies(module)) {
+                Some(rule) => next.extend(rule.produce(&module.params)),
+                None => next.push(module.clone()),
+            }
+        }
+        next
+    }
+
+    pub fn expand(&self, iterations: usize) -> Vec<Module> {
+        (0..iterations).fold(self.axiom.clone(), |modules, _| self.rewrite(&modules))
+    }
+
+    // Walks a turtle over the modules, starting at the origin and facing up
+    pub fn interpret(&self, modules: &[Module]) -> Vec<Segment> {
+        let mut turtle = Turtle::new();
+        turtle.set_width(self.width);
+        turtle.set_color(self.color);
+        for module in modules.iter() {
+            match module.symbol {
+                'F' | 'G' => turtle.forward(module.param(0).unwrap_or(self.step)),
+                'f' => {
+                    let p = turtle.position() + turtle.direction() * module.param(0).unwrap_or(self.step);
+                    turtle.jump_to(p);
+                }
+                '+' => turtle.left(module.param(0).unwrap_or(self.angle).to_radians()),
+                '-' => turtle.right(module.param(0).unwrap_or(self.angle).to_radians()),
+                '|' => turtle.left(PI),
+                '!' => {
+                    let width = module.param(0).unwrap_or(turtle.state.width * self.width_scale);
+                    turtle.set_width(width);
+                }
+                '[' => turtle.push(),
+                ']' => turtle.pop(),
+                _ => {}
+            }
+        }
+        turtle.take_path()
+    }
+
+    pub fn build(&self) -> Vec<Segment> {
+        self.interpret(&self.expand(self.iterations))
+    }
+}
+
+pub const PRESET_NAMES: [&str; 7] = [
+    "binary tree", "koch", "hilbert", "plant", "dragon", "stochastic plant", "parametric tree",
+];
+
+// the ii-th parameter, or default for a module written without it
+fn param(params: &[f32], ii: usize, default: f32) -> f32 {
+    params.get(ii).copied().unwrap_or(default)
+}
+
+pub fn preset(name: &str) -> Option<LSystem> {
+    let system = match name {
+        // the tree drawing_tree used to draw by hand, every branch 2/3 of the one before it
+        "binary tree" => LSystem::new("A(120)", 30., 12)
+            .rule(Rule::parametric('A', |p| {
+                let l = param(p, 0, 120.);
+                let mut m = vec![Module::with('!', &[map_range(l, 2., 120., 1., 10.)]), Module::with('F', &[l])];
+                m.extend(parse(&format!("[+A({})][-A({})]", l * 0.66, l * 0.66)));
+                m
+            }).when(|p| param(p, 0, 120.) > 2.))
+            .rule(Rule::parametric('A', |p| vec![Module::with('!', &[1.]), Module::with('F', &[param(p, 0, 120.)])])),
+        "koch" => LSystem::new("F--F--F", 60., 4)
+            .rule(Rule::new('F', "F+F--F+F")),
+        "hilbert" => LSystem::new("A", 90., 6)
+            .rule(Rule::new('A', "+BF-AFA-FB+"))
+            .rule(Rule::new('B', "-AF+BFB+FA-")),
+        "plant" => LSystem::new("-(65)X", 25., 6)
+            .rule(Rule::new('X', "F+[[X]-X]-F[-FX]+X"))
+            .rule(Rule::new('F', "FF")),
+        "dragon" => LSystem::new("FX", 90., 12)
+            .rule(Rule::new('X', "X+YF+"))
+            .rule(Rule::new('Y', "-FX-Y")),
+        "stochastic plant" => LSystem::new("F", 25.7, 5)
+            .rule(Rule::stochastic('F', &[(1., "F[+F]F[-F]F"), (1., "F[+F]F"), (1., "F[-F]F")])),
+        // branches get shorter and thinner by a random amount
+        "parametric tree" => LSystem::new("A(100,12)", 30., 9)
+            .rule(Rule::parametric('A', |p| {
+                let (l, w) = (param(p, 0, 100.), param(p, 1, 12.));
+                let left = random_range(20., 40.);
+                let right = random_range(20., 40.);
+                parse(&format!(
+                    "!({})F({})[+({})A({},{})][-({})A({},{})]",
+                    w, l, left, l * random_range(0.6, 0.8), w * 0.7, right, l * random_range(0.6, 0.8), w * 0.7,
+                ))
+            }).when(|p| param(p, 0, 100.) > 3.)),
+        _ => return None,
+    };
+    Some(system)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::turtle::{bounds, fit};
+
+    #[test]
+    fn rewriting() {
+        let koch = preset("koch").unwrap();
+        assert_eq!(to_string(&koch.expand(1)), "F+F--F+F--F+F--F+F--F+F--F+F");
+        assert_eq!(parse("A(1,0.5)+"), vec![Module::with('A', &[1., 0.5]), Module::new('+')]);
+
+        // stops growing once the branches get short
+        let tree = preset("binary tree").unwrap();
+        let modules = tree.expand(20);
+        assert_eq!(modules.iter().filter(|m| m.symbol == 'F').count(), 2usize.pow(11) - 1);
+
+        // a bare A grows like the axiom's
+        for name in ["binary tree", "parametric tree"] {
+            let mut system = preset(name).unwrap();
+            system.axiom = parse("A");
+            assert!(system.expand(2).iter().filter(|m| m.symbol == 'F').count() > 1);
+        }
+
+        let plant = preset("stochastic plant").unwrap();
+        let text = to_string(&plant.expand(1));
+        assert!(["F[+F]F[-F]F", "F[+F]F", "F[-F]F"].contains(&text.as_str()));
+    }
+
+    #[test]
+    fn turtle_walks_a_square() {
+        let mut square = LSystem::new("F+F+F+F", 90., 0);
+        square.set_step(2.);
+        let segments = square.build();
+        assert_eq!(segments.len(), 4);
+        assert!(segments[0].end.distance(vec2(0., 2.)) < 1e-5);
+        assert!(segments[3].end.length() < 1e-5);
+
+        let branches = LSystem::new("F[+F]F", 90., 0).build();
+        assert_eq!(branches[1].depth, 1);
+        assert!(branches[2].start.distance(branches[1].start) < 1e-5);
+        let fitted = fit(&branches, Rect::from_w_h(100., 100.));
+        assert!((bounds(&fitted).h() - 100.).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,214 @@
+use nannou::noise::{NoiseFn, OpenSimplex, Perlin, Seedable, Value, Worley};
+use crate::flow::Octaves;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseKind {
+    Perlin,
+    OpenSimplex,
+    Worley,
+    Value,
+    // sharp ridges out of folded perlin noise, looks like mountains or veins
+    Ridged,
+    // perlin sampled at positions moved by more perlin, swirly marble
+    DomainWarped,
+}
+
+impl NoiseKind {
+    pub fn next(self) -> Self {
+        match self {
+            NoiseKind::Perlin => NoiseKind::OpenSimplex,
+            NoiseKind::OpenSimplex => NoiseKind::Worley,
+            NoiseKind::Worley => NoiseKind::Value,
+            NoiseKind::Value => NoiseKind::Ridged,
+            NoiseKind::Ridged => NoiseKind::DomainWarped,
+            NoiseKind::DomainWarped => NoiseKind::Perlin,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NoiseKind::Perlin => "perlin",
+            NoiseKind::OpenSimplex => "open simplex",
+            NoiseKind::Worley => "worley",
+            NoiseKind::Value => "value",
+            NoiseKind::Ridged => "ridged",
+            NoiseKind::DomainWarped => "domain warped",
+        }
+    }
+}
+
+// The defaults give exactly the plain noise, one octave at frequency 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseSettings {
+    pub frequency: f64,
+    pub octaves: Octaves,
+    // how far the domain warped noise moves its samples
+    pub warp: f64,
+}
+
+impl Default for NoiseSettings {
+    fn default() -> Self {
+        Self { frequency: 1., octaves: Octaves::default(), warp: 1. }
+    }
+}
+
+// Any of the noise kinds behind one type, so a sketch can switch at runtime.
+// It is a NoiseFn itself, anything taking a noise function takes it.
+#[derive(Debug, Clone, Copy)]
+pub struct NoiseSource {
+    pub kind: NoiseKind,
+    pub settings: NoiseSettings,
+    seed: u32,
+    perlin: Perlin,
+    open_simplex: OpenSimplex,
+    worley: Worley,
+    value: Value,
+}
+
+// sums the octaves, sample gets the frequency of the octave
+fn fractal<F: Fn(f64) -> f64>(octaves: &Octaves, sample: F) -> f64 {
+    let (mut sum, mut norm, mut amp, mut freq) = (0., 0., 1., 1.);
+    for _ in 0..octaves.count.max(1) {
+        sum += sample(freq) * amp;
+        norm += amp;
+        amp *= octaves.persistence;
+        freq *= octaves.lacunarity;
+    }
+    sum / norm
+}
+
+// folded so the zero crossings become peaks, squared to sharpen them, back to -1..1
+fn ridge(v: f64) -> f64 {
+    (1. - v.abs()).powi(2) * 2. - 1.
+}
+
+impl NoiseSource {
+    pub fn new(kind: NoiseKind) -> Self {
+        Self {
+            kind,
+            settings: NoiseSettings::default(),
+            seed: 0,
+            perlin: Perlin::new(),
+            open_simplex: OpenSimplex::new(),
+            worley: Worley::new(),
+            value: Value::new(),
+        }
+    }
+
+    pub fn set_kind(&mut self, kind: NoiseKind) {
+        self.kind = kind;
+    }
+
+    pub fn set_settings(&mut self, settings: NoiseSettings) {
+        self.settings = settings;
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+        self.perlin = self.perlin.set_seed(seed);
+        self.open_simplex = self.open_simplex.set_seed(seed);
+        self.worley = self.worley.set_seed(seed);
+        self.value = self.value.set_seed(seed);
+    }
+
+    fn base_2d(&self, p: [f64; 2]) -> f64 {
+        match self.kind {
+            NoiseKind::OpenSimplex => self.open_simplex.get(p),
+            NoiseKind::Worley => self.worley.get(p),
+            NoiseKind::Value => self.value.get(p),
+            _ => self.perlin.get(p),
+        }
+    }
+
+    fn base_3d(&self, p: [f64; 3]) -> f64 {
+        match self.kind {
+            NoiseKind::OpenSimplex => self.open_simplex.get(p),
+            NoiseKind::Worley => self.worley.get(p),
+            NoiseKind::Value => self.value.get(p),
+            _ => self.perlin.get(p),
+        }
+    }
+
+    fn get_2d(&self, x: f64, y: f64) -> f64 {
+        let s = &self.settings;
+        let (x, y) = (x * s.frequency, y * s.frequency);
+        match self.kind {
+            NoiseKind::Ridged => fractal(&s.octaves, |f| ridge(self.base_2d([x * f, y * f]))),
+            NoiseKind::DomainWarped => {
+                let fbm = |x: f64, y: f64| fractal(&s.octaves, |f| self.base_2d([x * f, y * f]));
+                let qx = fbm(x, y);
+                let qy = fbm(x + 5.2, y + 1.3);
+                fbm(x + qx * s.warp, y + qy * s.warp)
+            }
+            _ => fractal(&s.octaves, |f| self.base_2d([x * f, y * f])),
+        }
+    }
+
+    fn get_3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        let s = &self.settings;
+        let (x, y, z) = (x * s.frequency, y * s.frequency, z * s.frequency);
+        match self.kind {
+            NoiseKind::Ridged => fractal(&s.octaves, |f| ridge(self.base_3d([x * f, y * f, z * f]))),
+            NoiseKind::DomainWarped => {
+                let fbm = |x: f64, y: f64, z: f64| fractal(&s.octaves, |f| self.base_3d([x * f, y * f, z * f]));
+                let qx = fbm(x, y, z);
+                let qy = fbm(x + 5.2, y + 1.3, z);
+                fbm(x + qx * s.warp, y + qy * s.warp, z)
+            }
+            _ => fractal(&s.octaves, |f| self.base_3d([x * f, y * f, z * f])),
+        }
+    }
+}
+
+impl Default for NoiseSource {
+    fn default() -> Self {
+        NoiseSource::new(NoiseKind::Perlin)
+    }
+}
+
+impl NoiseFn<[f64; 2]> for NoiseSource {
+    fn get(&self, point: [f64; 2]) -> f64 {
+        self.get_2d(point[0], point[1])
+    }
+}
+
+impl NoiseFn<[f64; 3]> for NoiseSource {
+    fn get(&self, point: [f64; 3]) -> f64 {
+        self.get_3d(point[0], point[1], point[2])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_match_plain_perlin() {
+        let mut source = NoiseSource::default();
+        source.set_seed(7);
+        let perlin = Perlin::new().set_seed(7);
+        for p in [[0.3, 0.7], [-2.1, 5.4], [10.2, -3.3]].iter() {
+            assert_eq!(source.get(*p), perlin.get(*p));
+        }
+    }
+
+    #[test]
+    fn every_kind_stays_in_range() {
+        let mut source = NoiseSource::default();
+        source.set_settings(NoiseSettings { octaves: Octaves::new(4, 2., 0.5), ..Default::default() });
+        let mut kind = NoiseKind::Perlin;
+        for _ in 0..6 {
+            source.set_kind(kind);
+            for ii in 0..50 {
+                let v = source.get([ii as f64 * 0.37, ii as f64 * -0.21, 0.5]);
+                assert!(v.is_finite() && v.abs() <= 1.5, "{} gave {}", kind.name(), v);
+            }
+            kind = kind.next();
+        }
+        assert_eq!(kind, NoiseKind::Perlin);
+    }
+}
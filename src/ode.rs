@@ -0,0 +1,146 @@
+use nannou::prelude::*;
+
+// Classic fourth order Runge-Kutta, for systems that don't depend on time
+pub fn rk4_step<F: Fn(Vec3) -> Vec3>(f: &F, y: Vec3, dt: f32) -> Vec3 {
+    let k1 = f(y);
+    let k2 = f(y + k1 * (dt / 2.));
+    let k3 = f(y + k2 * (dt / 2.));
+    let k4 = f(y + k3 * dt);
+    y + (k1 + k2 * 2. + k3 * 2. + k4) * (dt / 6.)
+}
+
+// Dormand-Prince, returns the fifth order result and how far the fourth order one is from it
+pub fn rk45_step<F: Fn(Vec3) -> Vec3>(f: &F, y: Vec3, dt: f32) -> (Vec3, f32) {
+    let k1 = f(y);
+    let k2 = f(y + k1 * (dt / 5.));
+    let k3 = f(y + (k1 * (3. / 40.) + k2 * (9. / 40.)) * dt);
+    let k4 = f(y + (k1 * (44. / 45.) - k2 * (56. / 15.) + k3 * (32. / 9.)) * dt);
+    let k5 = f(y + (k1 * (19372. / 6561.) - k2 * (25360. / 2187.) + k3 * (64448. / 6561.) - k4 * (212. / 729.)) * dt);
+    let k6 = f(y + (k1 * (9017. / 3168.) - k2 * (355. / 33.) + k3 * (46732. / 5247.) + k4 * (49. / 176.) - k5 * (5103. / 18656.)) * dt);
+    let y5 = y + (k1 * (35. / 384.) + k3 * (500. / 1113.) + k4 * (125. / 192.) - k5 * (2187. / 6784.) + k6 * (11. / 84.)) * dt;
+    let k7 = f(y5);
+    let y4 = y + (k1 * (5179. / 57600.) + k3 * (7571. / 16695.) + k4 * (393. / 640.) - k5 * (92097. / 339200.) + k6 * (187. / 2100.) + k7 * (1. / 40.)) * dt;
+    (y5, (y5 - y4).abs().max_element())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stepper {
+    // fixed steps of dt
+    Rk4 { dt: f32 },
+    // the step grows and shrinks to keep the error of every step under tolerance
+    Rk45 { tolerance: f32, min_dt: f32, max_dt: f32 },
+}
+
+impl Stepper {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stepper::Rk4 { .. } => "rk4",
+            Stepper::Rk45 { .. } => "rk45",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Integrator {
+    pub stepper: Stepper,
+    // the step size the adaptive stepper settled on
+    dt: f32,
+    steps: usize,
+}
+
+impl Integrator {
+    pub fn new(stepper: Stepper) -> Self {
+        let dt = match stepper {
+            Stepper::Rk4 { dt } => dt,
+            Stepper::Rk45 { min_dt, max_dt, .. } => (min_dt * max_dt).sqrt(),
+        };
+        Self { stepper, dt, steps: 0 }
+    }
+
+    pub fn rk4(dt: f32) -> Self {
+        Integrator::new(Stepper::Rk4 { dt })
+    }
+
+    pub fn rk45(tolerance: f32) -> Self {
+        Integrator::new(Stepper::Rk45 { tolerance, min_dt: 1e-5, max_dt: 0.1 })
+    }
+
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    // how many steps were taken so far, rejected ones included
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    // One step, never longer than max, returns the new state and the time it covered
+    pub fn step<F: Fn(Vec3) -> Vec3>(&mut self, f: &F, y: Vec3, max: f32) -> (Vec3, f32) {
+        match self.stepper {
+            Stepper::Rk4 { dt } => {
+                self.steps += 1;
+                let dt = dt.min(max);
+                (rk4_step(f, y, dt), dt)
+            }
+            Stepper::Rk45 { tolerance, min_dt, max_dt } => loop {
+                self.steps += 1;
+                let dt = self.dt.min(max);
+                let (next, error) = rk45_step(f, y, dt);
+                // the usual safety factor and limits on how fast the step may change
+                let factor = if error > 0. { 0.9 * (tolerance / error).powf(0.2) } else { 5. };
+                if error <= tolerance || dt <= min_dt {
+                    self.dt = (self.dt * factor.clamp(0.2, 5.)).clamp(min_dt, max_dt);
+                    return (next, dt);
+                }
+                self.dt = (self.dt * factor.max(0.1)).max(min_dt);
+            },
+        }
+    }
+
+    // moves the state forward by duration, in as many steps as it takes
+    pub fn advance<F: Fn(Vec3) -> Vec3>(&mut self, f: &F, mut y: Vec3, duration: f32) -> Vec3 {
+        let mut left = duration;
+        // whatever float error is left over is not worth another step
+        while left > duration * 1e-4 {
+            let (next, dt) = self.step(f, y, left);
+            y = next;
+            left -= dt;
+        }
+        y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // y' = -y, so y(t) = y(0) e^-t
+    fn decay(y: Vec3) -> Vec3 {
+        -y
+    }
+
+    #[test]
+    fn rk4_follows_exponential_decay() {
+        let mut integrator = Integrator::rk4(0.01);
+        let y = integrator.advance(&decay, vec3(1., 2., -3.), 1.);
+        let expected = vec3(1., 2., -3.) * (-1f32).exp();
+        assert!((y - expected).abs().max_element() < 1e-5);
+        assert_eq!(integrator.steps(), 100);
+    }
+
+    #[test]
+    fn rk45_adapts_the_step() {
+        let mut integrator = Integrator::rk45(1e-6);
+        let y = integrator.advance(&decay, vec3(1., 1., 1.), 2.);
+        assert!((y.x - (-2f32).exp()).abs() < 1e-4);
+        // a smooth problem needs far fewer steps than the fixed one
+        assert!(integrator.steps() < 100);
+
+        // a circle, the radius should not drift
+        let rotate = |p: Vec3| vec3(-p.y, p.x, 0.);
+        let mut integrator = Integrator::rk45(1e-6);
+        let p = integrator.advance(&rotate, vec3(1., 0., 0.), 10. * PI);
+        assert!((p.length() - 1.).abs() < 1e-3);
+        assert!(p.distance(vec3(1., 0., 0.)) < 1e-2);
+    }
+}
@@ -25,6 +25,15 @@ impl Particle {
         self.location = self.location.add(self.velocity);
     }
 
+    // Forces act directly on the velocity, every particle has the same mass
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.velocity = self.velocity.add(force);
+    }
+
+    pub fn limit_speed(&mut self, max_speed: f32) {
+        self.velocity = self.velocity.clamp_length_max(max_speed);
+    }
+
     pub fn display(&self, draw: &Draw) {
         (self.draw_fn)(draw, self.location, self.color);
     }
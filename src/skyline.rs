@@ -0,0 +1,306 @@
+use nannou::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Roof {
+    Flat,
+    // a triangle of this height
+    Peaked(f32),
+    // a narrow tapering top, like the old skyscrapers
+    Spire(f32),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    rect: Rect,
+    lit: bool,
+    color: Srgba<u8>,
+}
+
+// One building, a main block with a few setbacks stacked on it
+pub struct Platform {
+    pub rect: Rect,
+    pub color: Srgba<u8>,
+    pub stroke: Srgba<u8>,
+    pub tiers: Vec<Rect>,
+    pub roof: Roof,
+    pub antenna: Option<f32>,
+    windows: Vec<Window>,
+}
+
+const WINDOW_SPACING: f32 = 13.;
+
+fn window_color() -> Srgba<u8> {
+    let warm = [(255, 214, 150), (255, 236, 179), (203, 241, 245), (255, 196, 120)];
+    let (r, g, b) = warm[random_range(0, warm.len())];
+    srgba(r, g, b, 200)
+}
+
+fn lerp_color(a: Srgba<u8>, b: Srgba<u8>, t: f32) -> Srgba<u8> {
+    let l = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t) as u8;
+    srgba(l(a.red, b.red), l(a.green, b.green), l(a.blue, b.blue), a.alpha)
+}
+
+impl Platform {
+    pub fn new(rect: Rect, color: Srgba<u8>, stroke: Srgba<u8>) -> Self {
+        let mut platform = Self {
+            rect,
+            color,
+            stroke,
+            tiers: vec![],
+            roof: Roof::Flat,
+            antenna: None,
+            windows: vec![],
+        };
+        platform.make_windows();
+        platform
+    }
+
+    // A random building standing on bottom, centered on x
+    pub fn generate(x: f32, w: f32, h: f32, bottom: f32, color: Srgba<u8>, stroke: Srgba<u8>) -> Self {
+        let rect = Rect::from_x_y_w_h(x, bottom + h / 2., w, h);
+
+        // every setback is narrower and shorter than the one below
+        let mut tiers = vec![];
+        let mut top = rect;
+        while random::<f32>() < 0.5 && top.w() > 30. && tiers.len() < 3 {
+            let tw = top.w() * random_range(0.5, 0.8);
+            let th = top.h() * random_range(0.15, 0.4);
+            let tier = Rect::from_x_y_w_h(top.x(), top.top() + th / 2., tw, th);
+            tiers.push(tier);
+            top = tier;
+        }
+        let roof = match random_range(0, 6) {
+            0 => Roof::Peaked(top.w() * random_range(0.2, 0.5)),
+            1 => Roof::Spire(random_range(20., 60.)),
+            _ => Roof::Flat,
+        };
+        let antenna = if random::<f32>() < 0.3 { Some(random_range(10., 40.)) } else { None };
+        let mut platform = Self { rect, color, stroke, tiers, roof, antenna, windows: vec![] };
+        platform.make_windows();
+        platform
+    }
+
+    // every block of the building, the main one first
+    pub fn rects(&self) -> Vec<Rect> {
+        let mut rects = vec![self.rect];
+        rects.extend(self.tiers.iter().copied());
+        rects
+    }
+
+    // The parts of the tops that nothing stands on, as rects with no height.
+    // A block with a setback on it leaves a ledge on either side.
+    pub fn exposed_tops(&self) -> Vec<Rect> {
+        let rects = self.rects();
+        let ledge = |left: f32, right: f32, top: f32| Rect::from_corners(vec2(left, top), vec2(right, top));
+        let mut tops = vec![];
+        for (ii, r) in rects.iter().enumerate() {
+            match rects.get(ii + 1) {
+                Some(above) => {
+                    if above.left() > r.left() {
+                        tops.push(ledge(r.left(), above.left(), r.top()));
+                    }
+                    if above.right() < r.right() {
+                        tops.push(ledge(above.right(), r.right(), r.top()));
+                    }
+                }
+                None => tops.push(ledge(r.left(), r.right(), r.top())),
+            }
+        }
+        tops
+    }
+
+    fn top(&self) -> Rect {
+        *self.tiers.last().unwrap_or(&self.rect)
+    }
+
+    fn make_windows(&mut self) {
+        self.windows.clear();
+        for r in self.rects() {
+            let building_r = r.pad(3.);
+            let w_row = (building_r.w() / WINDOW_SPACING).floor();
+            let w_col = (building_r.h() / WINDOW_SPACING).floor();
+            let wh = Vec2::new(5., 7.);
+            // center the grid in the block
+            let start = vec2(
+                building_r.x() - (w_row - 1.) * WINDOW_SPACING / 2.,
+                building_r.y() - (w_col - 1.) * WINDOW_SPACING / 2.,
+            );
+            for ii in 0..w_row as usize {
+                for jj in 0..w_col as usize {
+                    let p = start + vec2(ii as f32, jj as f32) * WINDOW_SPACING;
+                    self.windows.push(Window {
+                        rect: Rect::from_x_y_w_h(p.x, p.y, wh.x, wh.y),
+                        lit: random::<f32>() < 0.3,
+                        color: window_color(),
+                    });
+                }
+            }
+        }
+    }
+
+    // people come home and go to bed, windows switch on and off now and then
+    pub fn update(&mut self) {
+        for window in self.windows.iter_mut() {
+            if random::<f32>() < 0.0005 {
+                window.lit = !window.lit;
+            }
+        }
+    }
+
+    // flash is the lightning brightness 0..1, lit by the color of the lightning
+    pub fn display(&self, draw: &Draw, flash: f32, flash_color: Srgba<u8>) {
+        let color = lerp_color(self.color, flash_color, flash * 0.25);
+        let stroke = lerp_color(self.stroke, flash_color, flash * 0.4);
+        for r in self.rects() {
+            draw.rect()
+                .xy(r.xy())
+                .wh(r.wh())
+                .stroke(stroke)
+                .stroke_weight(3.)
+                .color(color);
+        }
+
+        let top = self.top();
+        let mut peak = top.top();
+        match self.roof {
+            Roof::Flat => {}
+            Roof::Peaked(h) => {
+                draw.tri()
+                    .points(top.top_left(), top.top_right(), vec2(top.x(), top.top() + h))
+                    .color(color);
+                peak += h;
+            }
+            Roof::Spire(h) => {
+                let w = top.w() * 0.15;
+                draw.tri()
+                    .points(vec2(top.x() - w, top.top()), vec2(top.x() + w, top.top()), vec2(top.x(), top.top() + h))
+                    .color(color);
+                peak += h;
+            }
+        }
+        if let Some(h) = self.antenna {
+            draw.line()
+                .start(vec2(top.x(), peak))
+                .end(vec2(top.x(), peak + h))
+                .weight(1.5)
+                .color(stroke);
+            // aircraft warning light
+            draw.ellipse().x_y(top.x(), peak + h).radius(1.5).color(srgba(255u8, 60, 60, 200));
+        }
+
+        for window in self.windows.iter().filter(|w| w.lit || flash > 0.) {
+            let c = if window.lit { window.color } else { srgba(flash_color.red, flash_color.green, flash_color.blue, (flash * 80.) as u8) };
+            draw.rect().xy(window.rect.xy()).wh(window.rect.wh()).color(c);
+        }
+    }
+}
+
+// A row of buildings along the bottom of rect.
+// depth 0 is the front row, further rows are taller so they show above it, and fade into the sky color.
+pub fn make_skyline(rect: Rect, count: usize, depth: f32) -> Vec<Platform> {
+    let front = srgba(27, 38, 44, 255);
+    let sky = srgba(15, 20, 30, 255);
+    let color = lerp_color(front, sky, depth * 0.7);
+    let stroke = lerp_color(srgba(28, 39, 45, 225), sky, depth * 0.7);
+    let w = rect.w() / count as f32;
+    (0..count + 1).map(|ii| {
+        let x = rect.left() + w * ii as f32 + random_range(-0.2, 0.2) * w;
+        let bw = w * random_range(0.7, 1.1);
+        let h = random_range(100., 300.) * (1. + depth);
+        Platform::generate(x, bw, h, rect.bottom(), color, stroke)
+    }).collect()
+}
+
+pub struct Layer {
+    pub depth: f32,
+    pub platforms: Vec<Platform>,
+}
+
+// Rows of buildings at different depths, the further a row is the more it moves
+// when the view shifts, the front row stays put since things collide with it
+pub struct Skyline {
+    pub layers: Vec<Layer>,
+}
+
+impl Skyline {
+    // layers go from the back to the front, the last one is the one things collide with
+    pub fn new(rect: Rect, count: usize, layers: usize) -> Self {
+        let layers = (0..layers).rev().map(|ii| {
+            let depth = ii as f32 / layers.max(1) as f32;
+            Layer { depth, platforms: make_skyline(rect, count, depth) }
+        }).collect();
+        Self { layers }
+    }
+
+    // nothing when there are no layers
+    pub fn front(&self) -> Option<&[Platform]> {
+        self.layers.last().map(|l| l.platforms.as_slice())
+    }
+
+    // all the rects of the front row, for collisions
+    pub fn collision_rects(&self) -> Vec<Rect> {
+        self.front().unwrap_or_default().iter().flat_map(|p| p.rects()).collect()
+    }
+
+    // the tops of the front row the weather can settle on
+    pub fn surfaces(&self) -> Vec<Rect> {
+        self.front().unwrap_or_default().iter().flat_map(|p| p.exposed_tops()).collect()
+    }
+
+    pub fn update(&mut self) {
+        for layer in self.layers.iter_mut() {
+            for platform in layer.platforms.iter_mut() {
+                platform.update();
+            }
+        }
+    }
+
+    // shift is how far the view moved, far rows are lit less by the lightning
+    pub fn display(&self, draw: &Draw, shift: f32, flash: f32, flash_color: Srgba<u8>) {
+        for layer in self.layers.iter() {
+            let draw = draw.x_y(shift * layer.depth, 0.);
+            for platform in layer.platforms.iter() {
+                platform.display(&draw, flash * (1. - layer.depth * 0.5), flash_color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setbacks_leave_ledges() {
+        let mut platform = Platform::new(Rect::from_x_y_w_h(0., 50., 100., 100.), srgba(0, 0, 0, 255), srgba(0, 0, 0, 255));
+        platform.tiers.push(Rect::from_x_y_w_h(10., 120., 40., 40.));
+        let tops = platform.exposed_tops();
+        assert_eq!(tops.len(), 3);
+        assert_eq!((tops[0].left(), tops[0].right(), tops[0].top()), (-50., -10., 100.));
+        assert_eq!((tops[1].left(), tops[1].right(), tops[1].top()), (30., 50., 100.));
+        assert_eq!((tops[2].left(), tops[2].right(), tops[2].top()), (-10., 30., 140.));
+
+        // 94 by 94 inside the padding fits 7 by 7 windows, the 34 by 34 setback 2 by 2
+        assert_eq!(platform.windows.len(), 49);
+        platform.make_windows();
+        assert_eq!(platform.windows.len(), 53);
+    }
+
+    #[test]
+    fn skyline_rows() {
+        let r = Rect::from_w_h(800., 600.);
+        let skyline = Skyline::new(r, 5, 3);
+        assert_eq!(skyline.layers.len(), 3);
+        // the front row is the last one, at depth 0
+        assert_eq!(skyline.layers[2].depth, 0.);
+        assert_eq!(skyline.front().unwrap().len(), 6);
+        for p in skyline.front().unwrap() {
+            assert_eq!(p.rect.bottom(), r.bottom());
+        }
+        assert!(!skyline.surfaces().is_empty());
+
+        let empty = Skyline { layers: vec![] };
+        assert!(empty.front().is_none());
+        assert!(empty.collision_rects().is_empty());
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+use nannou::geom::{Rect, Vec2};
+
+// Common interface for the spatial indexes, items are referred to by an id,
+// usually the index of the item in the sketch's own Vec
+pub trait SpatialIndex {
+    fn insert(&mut self, id: usize, position: Vec2);
+    fn update(&mut self, id: usize, position: Vec2);
+    fn remove(&mut self, id: usize);
+    fn clear(&mut self);
+    fn query_radius(&self, center: Vec2, r: f32) -> Vec<usize>;
+    fn query_rect(&self, rect: Rect) -> Vec<usize>;
+}
+
+// Axis aligned box used internally, min is bottom left, max is top right
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bounds {
+    min: Vec2,
+    max: Vec2,
+}
+
+impl Bounds {
+    fn point(p: Vec2) -> Self {
+        Self { min: p, max: p }
+    }
+
+    fn from_rect(rect: Rect) -> Self {
+        Self {
+            min: Vec2::new(rect.left(), rect.bottom()),
+            max: Vec2::new(rect.right(), rect.top()),
+        }
+    }
+
+    fn around(center: Vec2, r: f32) -> Self {
+        Self { min: center - Vec2::splat(r), max: center + Vec2::splat(r) }
+    }
+
+    fn overlaps(&self, other: &Bounds) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x
+            && self.min.y <= other.max.y && self.max.y >= other.min.y
+    }
+
+    fn contains(&self, p: Vec2) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.max.y
+    }
+
+    // squared distance from the point to the closest point of the box
+    fn distance_squared(&self, p: Vec2) -> f32 {
+        p.distance_squared(p.max(self.min).min(self.max))
+    }
+}
+
+// Uniform grid hash, works best when items are evenly spread and about the cell size.
+// Besides points it can store rects, which are added to every cell they cover.
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    items: HashMap<usize, Bounds>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            items: HashMap::new(),
+        }
+    }
+
+    pub fn insert_rect(&mut self, id: usize, rect: Rect) {
+        self.insert_bounds(id, Bounds::from_rect(rect));
+    }
+
+    pub fn update_rect(&mut self, id: usize, rect: Rect) {
+        self.remove(id);
+        self.insert_rect(id, rect);
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    fn cell(&self, p: Vec2) -> (i32, i32) {
+        ((p.x / self.cell_size).floor() as i32, (p.y / self.cell_size).floor() as i32)
+    }
+
+    // every cell key touched by the bounds
+    fn cells_for(&self, b: &Bounds) -> impl Iterator<Item=(i32, i32)> {
+        let (x0, y0) = self.cell(b.min);
+        let (x1, y1) = self.cell(b.max);
+        (x0..=x1).flat_map(move |x| (y0..=y1).map(move |y| (x, y)))
+    }
+
+    fn insert_bounds(&mut self, id: usize, b: Bounds) {
+        if self.items.contains_key(&id) {
+            self.remove(id);
+        }
+        let keys: Vec<(i32, i32)> = self.cells_for(&b).collect();
+        for key in keys {
+            self.cells.entry(key).or_default().push(id);
+        }
+        self.items.insert(id, b);
+    }
+
+    // ids from all cells overlapping the bounds, each id only once
+    fn candidates(&self, b: &Bounds) -> Vec<usize> {
+        let mut found = vec![];
+        for key in self.cells_for(b) {
+            if let Some(ids) = self.cells.get(&key) {
+                found.extend_from_slice(ids);
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+impl SpatialIndex for SpatialHash {
+    fn insert(&mut self, id: usize, position: Vec2) {
+        self.insert_bounds(id, Bounds::point(position));
+    }
+
+    fn update(&mut self, id: usize, position: Vec2) {
+        // only touch the cells when the item moved to another one
+        if let Some(b) = self.items.get(&id) {
+            if b.min == b.max && self.cell(b.min) == self.cell(position) {
+                self.items.insert(id, Bounds::point(position));
+                return;
+            }
+        }
+        self.insert(id, position);
+    }
+
+    fn remove(&mut self, id: usize) {
+        if let Some(b) = self.items.remove(&id) {
+            let keys: Vec<(i32, i32)> = self.cells_for(&b).collect();
+            for key in keys {
+                if let Some(ids) = self.cells.get_mut(&key) {
+                    ids.retain(|v| *v != id);
+                    if ids.is_empty() {
+                        self.cells.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.cells.clear();
+        self.items.clear();
+    }
+
+    fn query_radius(&self, center: Vec2, r: f32) -> Vec<usize> {
+        let mut found = self.candidates(&Bounds::around(center, r));
+        found.retain(|id| self.items[id].distance_squared(center) <= r * r);
+        found
+    }
+
+    fn query_rect(&self, rect: Rect) -> Vec<usize> {
+        let b = Bounds::from_rect(rect);
+        let mut found = self.candidates(&b);
+        found.retain(|id| self.items[id].overlaps(&b));
+        found
+    }
+}
+
+struct QuadNode {
+    bounds: Bounds,
+    depth: usize,
+    items: Vec<(usize, Vec2)>,
+    children: Option<[usize; 4]>,
+}
+
+// Point quadtree, adapts to clustered items better than the grid hash.
+// Nodes live in a flat Vec and refer to their children by index.
+pub struct QuadTree {
+    nodes: Vec<QuadNode>,
+    positions: HashMap<usize, Vec2>,
+    capacity: usize,
+    max_depth: usize,
+}
+
+impl QuadTree {
+    pub fn new(rect: Rect, capacity: usize) -> Self {
+        let mut tree = Self {
+            nodes: vec![],
+            positions: HashMap::new(),
+            capacity: capacity.max(1),
+            max_depth: 8,
+        };
+        tree.reset(Bounds::from_rect(rect));
+        tree
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    // Items outside of the root bounds are not stored
+    pub fn contains(&self, p: Vec2) -> bool {
+        self.nodes[0].bounds.contains(p)
+    }
+
+    fn reset(&mut self, bounds: Bounds) {
+        self.nodes.clear();
+        self.positions.clear();
+        self.nodes.push(QuadNode { bounds, depth: 0, items: vec![], children: None });
+    }
+
+    fn child_for(&self, node: usize, p: Vec2) -> usize {
+        let children = self.nodes[node].children.unwrap();
+        let b = self.nodes[node].bounds;
+        let mid = (b.min + b.max) * 0.5;
+        let ix = if p.x < mid.x { 0 } else { 1 };
+        let iy = if p.y < mid.y { 0 } else { 2 };
+        children[ix + iy]
+    }
+
+    fn split(&mut self, node: usize) {
+        let b = self.nodes[node].bounds;
+        let depth = self.nodes[node].depth + 1;
+        let mid = (b.min + b.max) * 0.5;
+        // bottom left, bottom right, top left, top right
+        let quads = [
+            Bounds { min: b.min, max: mid },
+            Bounds { min: Vec2::new(mid.x, b.min.y), max: Vec2::new(b.max.x, mid.y) },
+            Bounds { min: Vec2::new(b.min.x, mid.y), max: Vec2::new(mid.x, b.max.y) },
+            Bounds { min: mid, max: b.max },
+        ];
+        let mut children = [0; 4];
+        for (ii, bounds) in quads.iter().enumerate() {
+            children[ii] = self.nodes.len();
+            self.nodes.push(QuadNode { bounds: *bounds, depth, items: vec![], children: None });
+        }
+        self.nodes[node].children = Some(children);
+        let items = std::mem::take(&mut self.nodes[node].items);
+        for (id, p) in items {
+            let child = self.child_for(node, p);
+            self.nodes[child].items.push((id, p));
+        }
+    }
+
+    fn leaf_for(&self, p: Vec2) -> usize {
+        let mut node = 0;
+        while self.nodes[node].children.is_some() {
+            node = self.child_for(node, p);
+        }
+        node
+    }
+
+    fn collect(&self, b: &Bounds, found: &mut Vec<(usize, Vec2)>) {
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let n = &self.nodes[node];
+            if !n.bounds.overlaps(b) {
+                continue;
+            }
+            match n.children {
+                Some(children) => stack.extend_from_slice(&children),
+                None => found.extend(n.items.iter().filter(|(_, p)| b.contains(*p))),
+            }
+        }
+    }
+}
+
+impl SpatialIndex for QuadTree {
+    fn insert(&mut self, id: usize, position: Vec2) {
+        if self.positions.contains_key(&id) {
+            self.remove(id);
+        }
+        if !self.contains(position) {
+            return;
+        }
+        let mut node = self.leaf_for(position);
+        while self.nodes[node].items.len() >= self.capacity && self.nodes[node].depth < self.max_depth {
+            self.split(node);
+            node = self.child_for(node, position);
+        }
+        self.nodes[node].items.push((id, position));
+        self.positions.insert(id, position);
+    }
+
+    fn update(&mut self, id: usize, position: Vec2) {
+        // moved out of the root bounds, it is not stored anymore
+        if !self.contains(position) {
+            self.remove(id);
+            return;
+        }
+        if let Some(old) = self.positions.get(&id).copied() {
+            let node = self.leaf_for(old);
+            if node == self.leaf_for(position) {
+                for item in self.nodes[node].items.iter_mut().filter(|(v, _)| *v == id) {
+                    item.1 = position;
+                }
+                self.positions.insert(id, position);
+                return;
+            }
+        }
+        self.insert(id, position);
+    }
+
+    fn remove(&mut self, id: usize) {
+        if let Some(p) = self.positions.remove(&id) {
+            let node = self.leaf_for(p);
+            self.nodes[node].items.retain(|(v, _)| *v != id);
+        }
+    }
+
+    fn clear(&mut self) {
+        let bounds = self.nodes[0].bounds;
+        self.reset(bounds);
+    }
+
+    fn query_radius(&self, center: Vec2, r: f32) -> Vec<usize> {
+        let mut found = vec![];
+        self.collect(&Bounds::around(center, r), &mut found);
+        found.into_iter()
+            .filter(|(_, p)| p.distance_squared(center) <= r * r)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    fn query_rect(&self, rect: Rect) -> Vec<usize> {
+        let mut found = vec![];
+        self.collect(&Bounds::from_rect(rect), &mut found);
+        found.into_iter().map(|(id, _)| id).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points() -> Vec<Vec2> {
+        (0..400).map(|ii| Vec2::new((ii % 20) as f32 * 10. - 100., (ii / 20) as f32 * 10. - 100.)).collect()
+    }
+
+    fn brute_force(points: &[Vec2], center: Vec2, r: f32) -> Vec<usize> {
+        (0..points.len()).filter(|ii| points[*ii].distance(center) <= r).collect()
+    }
+
+    fn check_index(index: &mut impl SpatialIndex) {
+        let points = points();
+        for (ii, p) in points.iter().enumerate() {
+            index.insert(ii, *p);
+        }
+        let mut found = index.query_radius(Vec2::new(5., -3.), 25.);
+        found.sort();
+        assert_eq!(found, brute_force(&points, Vec2::new(5., -3.), 25.));
+
+        let mut found = index.query_rect(Rect::from_x_y_w_h(0., 0., 20., 20.));
+        found.sort();
+        assert_eq!(found.len(), 9);
+
+        index.update(0, Vec2::new(55., 55.));
+        assert!(index.query_radius(Vec2::new(55., 55.), 1.).contains(&0));
+        assert!(!index.query_radius(Vec2::new(-100., -100.), 1.).contains(&0));
+
+        index.remove(0);
+        assert!(!index.query_radius(Vec2::new(55., 55.), 1.).contains(&0));
+
+        index.clear();
+        assert!(index.query_radius(Vec2::ZERO, 500.).is_empty());
+    }
+
+    #[test]
+    fn spatial_hash_queries() {
+        check_index(&mut SpatialHash::new(16.));
+    }
+
+    #[test]
+    fn quadtree_queries() {
+        let mut tree = QuadTree::new(Rect::from_x_y_w_h(0., 0., 400., 400.), 4);
+        check_index(&mut tree);
+        // leaving the root bounds drops the item
+        tree.insert(1, Vec2::new(190., 190.));
+        tree.update(1, Vec2::new(250., 190.));
+        assert!(tree.query_rect(Rect::from_x_y_w_h(0., 0., 1000., 1000.)).is_empty());
+    }
+
+    #[test]
+    fn spatial_hash_rects() {
+        let mut index = SpatialHash::new(10.);
+        index.insert_rect(7, Rect::from_x_y_w_h(0., 0., 100., 20.));
+        assert_eq!(index.query_radius(Vec2::new(45., 0.), 1.), vec![7]);
+        assert_eq!(index.query_radius(Vec2::new(0., 14.), 5.), vec![7]);
+        assert!(index.query_radius(Vec2::new(0., 16.), 5.).is_empty());
+    }
+}
@@ -0,0 +1,275 @@
+use nannou::prelude::*;
+use crate::flow::FlowField;
+use crate::spatial::{SpatialHash, SpatialIndex};
+
+#[derive(Debug, Clone)]
+pub struct StreamlineParams {
+    // no two lines get closer than this, except where they end
+    pub separation: f32,
+    // a growing line stops when it gets this close to another line, as a fraction of separation
+    pub test_ratio: f32,
+    // length of one integration step in pixels
+    pub step: f32,
+    pub max_steps: usize,
+    // lines with fewer points than this are thrown away
+    pub min_points: usize,
+    // the field is considered zero below this, lines stop there
+    pub min_speed: f32,
+}
+
+impl Default for StreamlineParams {
+    fn default() -> Self {
+        Self {
+            separation: 12.,
+            test_ratio: 0.5,
+            step: 2.,
+            max_steps: 400,
+            min_points: 5,
+            min_speed: 1e-4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Streamline {
+    pub points: Vec<Vec2>,
+}
+
+impl Streamline {
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn length(&self) -> f32 {
+        self.points.windows(2).fold(0., |l, w| l + w[0].distance(w[1]))
+    }
+
+    // 0 at the ends, 1 in the middle, for lines that get thinner towards the ends
+    pub fn taper(&self, ii: usize) -> f32 {
+        if self.points.len() < 2 {
+            return 1.;
+        }
+        let t = ii as f32 / (self.points.len() - 1) as f32;
+        (t * PI).sin()
+    }
+}
+
+// One step along the field, midpoint method on the normalized direction
+fn advance<F: FlowField + ?Sized>(field: &F, p: Vec2, time: f32, step: f32, min_speed: f32) -> Option<Vec2> {
+    let v = field.sample(p, time);
+    if v.length() < min_speed {
+        return None;
+    }
+    let mid = p + v.normalize() * step * 0.5;
+    let v = field.sample(mid, time);
+    if v.length() < min_speed {
+        return None;
+    }
+    Some(p + v.normalize() * step)
+}
+
+// What every step of a trace needs besides where it is
+struct Tracer<'a, F: ?Sized> {
+    field: &'a F,
+    time: f32,
+    rect: Rect,
+    params: &'a StreamlineParams,
+}
+
+impl<'a, F: FlowField + ?Sized> Tracer<'a, F> {
+    // Follows the field from start in one direction (step can be negative) until it
+    // leaves rect, dies out, gets too close to occupied points or runs into the line itself.
+    // own has the points of the line so far, indexed by where they are along it with start at origin.
+    fn trace_direction(&self, start: Vec2, step: f32, occupied: &SpatialHash, own: &mut SpatialHash, origin: usize) -> Vec<Vec2> {
+        let params = self.params;
+        let d_test = params.separation * params.test_ratio;
+        // the neighbours along the line are always close, they don't count as running into it
+        let skip = (d_test / params.step.abs()).ceil() as usize + 2;
+        let mut points = vec![];
+        let mut p = start;
+        for ii in 0..params.max_steps {
+            p = match advance(self.field, p, self.time, step, params.min_speed) {
+                Some(p) => p,
+                None => break,
+            };
+            if !self.rect.contains(p) || !occupied.query_radius(p, d_test).is_empty() {
+                break;
+            }
+            let index = if step < 0. { origin - 1 - ii } else { origin + 1 + ii };
+            if own.query_radius(p, d_test).iter().any(|jj| jj.abs_diff(index) > skip) {
+                break;
+            }
+            own.insert(index, p);
+            points.push(p);
+        }
+        points
+    }
+}
+
+// A line through start going both ways along the field, points go in the direction of the flow.
+// The forward half also stops where it runs into the backward one.
+pub fn trace<F: FlowField + ?Sized>(
+    field: &F, start: Vec2, time: f32, rect: Rect,
+    params: &StreamlineParams, occupied: &SpatialHash,
+) -> Streamline {
+    let tracer = Tracer { field, time, rect, params };
+    let mut own = SpatialHash::new(params.separation);
+    let origin = params.max_steps;
+    own.insert(origin, start);
+    let mut points = tracer.trace_direction(start, -params.step, occupied, &mut own, origin);
+    points.reverse();
+    points.push(start);
+    points.extend(tracer.trace_direction(start, params.step, occupied, &mut own, origin));
+    Streamline { points }
+}
+
+// Evenly spaced streamlines, after Jobard and Lefer.
+// Every accepted line seeds new ones at separation distance on both sides, when
+// that runs out the rest of the rect is covered from a grid of seeds.
+pub fn evenly_spaced<F: FlowField + ?Sized>(field: &F, rect: Rect, time: f32, params: &StreamlineParams) -> Vec<Streamline> {
+    let mut occupied = SpatialHash::new(params.separation);
+    let mut lines: Vec<Streamline> = vec![];
+
+    let mut grid_seeds = vec![];
+    let mut y = rect.bottom() + params.separation / 2.;
+    while y < rect.top() {
+        let mut x = rect.left() + params.separation / 2.;
+        while x < rect.right() {
+            grid_seeds.push(vec2(x, y));
+            x += params.separation;
+        }
+        y += params.separation;
+    }
+    // start from the middle, it tends to give the longest first line
+    grid_seeds.push(rect.xy());
+
+    let mut queue: Vec<usize> = vec![];
+    while let Some(seed) = grid_seeds.pop() {
+        if add_line(field, seed, time, rect, params, &mut occupied, &mut lines) {
+            queue.push(lines.len() - 1);
+        }
+        while let Some(ll) = queue.pop() {
+            for seed in candidate_seeds(field, &lines[ll], time, params) {
+                if rect.contains(seed) && add_line(field, seed, time, rect, params, &mut occupied, &mut lines) {
+                    queue.push(lines.len() - 1);
+                }
+            }
+        }
+    }
+    lines
+}
+
+const SEED_TOLERANCE: f32 = 0.99;
+
+// traces a line from seed and keeps it if the seed is free and the line long enough
+fn add_line<F: FlowField + ?Sized>(
+    field: &F, seed: Vec2, time: f32, rect: Rect, params: &StreamlineParams,
+    occupied: &mut SpatialHash, lines: &mut Vec<Streamline>,
+) -> bool {
+    // a little under separation, the candidate seeds are exactly that far from their line
+    if !occupied.query_radius(seed, params.separation * SEED_TOLERANCE).is_empty() {
+        return false;
+    }
+    let line = trace(field, seed, time, rect, params, occupied);
+    if line.len() < params.min_points {
+        return false;
+    }
+    let first = occupied.len();
+    for (ii, p) in line.points.iter().enumerate() {
+        occupied.insert(first + ii, *p);
+    }
+    lines.push(line);
+    true
+}
+
+// points at separation distance on both sides of every point of the line
+fn candidate_seeds<F: FlowField + ?Sized>(field: &F, line: &Streamline, time: f32, params: &StreamlineParams) -> Vec<Vec2> {
+    let mut seeds = vec![];
+    for p in line.points.iter().step_by(2) {
+        let n = field.sample(*p, time).normalize_or_zero().perp();
+        seeds.push(*p + n * params.separation);
+        seeds.push(*p - n * params.separation);
+    }
+    seeds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streamlines_keep_their_distance() {
+        let field = |p: Vec2, _t: f32| vec2(1., (p.x * 0.02).sin() * 0.5);
+        let rect = Rect::from_w_h(300., 300.);
+        let params = StreamlineParams::default();
+        let lines = evenly_spaced(&field, rect, 0., &params);
+        assert!(lines.len() > 5);
+        let d_test = params.separation * params.test_ratio;
+        for (ii, a) in lines.iter().enumerate() {
+            assert!(a.points.iter().all(|p| rect.contains(*p)));
+            for b in lines.iter().skip(ii + 1) {
+                for p in a.points.iter() {
+                    assert!(b.points.iter().all(|q| p.distance(*q) > d_test * 0.9));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stops_at_singularities() {
+        // everything flows into the center and stops there
+        let field = |p: Vec2, _t: f32| if p.length() < 5. { Vec2::ZERO } else { -p };
+        let line = trace(&field, vec2(100., 0.), 0., Rect::from_w_h(400., 400.), &StreamlineParams::default(), &SpatialHash::new(10.));
+        assert!(line.points.last().unwrap().length() < 7.);
+        // backwards it flows out until it leaves the rect
+        assert!(line.points[0].x > 195.);
+    }
+
+    #[test]
+    fn green_growth_streamlines() {
+        use crate::flow::{FlowMode, NoiseFlow};
+        use crate::noise_source::{NoiseKind, NoiseSource};
+        // the field and the settings green_growth uses
+        let field = NoiseFlow::new(NoiseSource::new(NoiseKind::Perlin), FlowMode::Curl);
+        let scaled = |p: Vec2, time: f32| field.sample(p * 0.003, time);
+        let params = StreamlineParams { separation: 10., test_ratio: 1., max_steps: 300, min_points: 10, ..Default::default() };
+        let lines = evenly_spaced(&scaled, Rect::from_w_h(512., 512.), 0., &params);
+        assert!(lines.len() > 10);
+        for (ii, a) in lines.iter().enumerate() {
+            assert!(a.len() >= params.min_points);
+            // thin at both ends, full width in the middle
+            let last = a.len() - 1;
+            assert!(a.taper(0) < 1e-6 && a.taper(last) < 1e-6);
+            assert!(a.taper(last / 2) > 0.9);
+            for b in lines.iter().skip(ii + 1) {
+                for p in a.points.iter() {
+                    assert!(b.points.iter().all(|q| p.distance(*q) >= params.separation * SEED_TOLERANCE - 1e-3));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn halves_meet_around_a_loop() {
+        // circles around the center, the two halves meet on the far side
+        let field = |p: Vec2, _t: f32| p.perp();
+        let params = StreamlineParams::default();
+        let line = trace(&field, vec2(100., 0.), 0., Rect::from_w_h(400., 400.), &params, &SpatialHash::new(10.));
+        assert!(line.length() < TAU * 100.);
+        assert!(line.length() > TAU * 100. - params.separation * 2.);
+
+        // seeds right at separation from a line are taken
+        let field = |_p: Vec2, _t: f32| vec2(1., 0.);
+        let mut occupied = SpatialHash::new(params.separation);
+        let mut lines = vec![];
+        assert!(add_line(&field, Vec2::ZERO, 0., Rect::from_w_h(200., 200.), &params, &mut occupied, &mut lines));
+        for seed in candidate_seeds(&field, &lines[0], 0., &params).into_iter().take(2) {
+            assert!(add_line(&field, seed, 0., Rect::from_w_h(200., 200.), &params, &mut occupied, &mut lines));
+        }
+        assert_eq!(lines.len(), 3);
+    }
+}
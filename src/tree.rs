@@ -0,0 +1,319 @@
+use nannou::glam::Mat2;
+use nannou::prelude::*;
+use crate::get_random_green;
+use crate::turtle::Segment;
+
+#[derive(Debug, Clone)]
+pub struct TreeParams {
+    // how many times the branches split
+    pub depth: usize,
+    pub trunk_length: f32,
+    pub trunk_width: f32,
+    // every branch is this much of its parent's length, picked at random in the range
+    pub length_ratio: (f32, f32),
+    // how far from the parent's direction a branch turns, in radians, either side
+    pub angle: (f32, f32),
+    // chance of a third branch at a split
+    pub third_branch: f32,
+    // the width is multiplied by this along every branch
+    pub taper: f32,
+    // branches this deep and deeper get leaves at the end
+    pub leaf_depth: usize,
+    pub leaves_per_cluster: usize,
+    pub leaf_size: f32,
+    pub bark: Srgba<u8>,
+}
+
+impl Default for TreeParams {
+    fn default() -> Self {
+        Self {
+            depth: 8,
+            trunk_length: 110.,
+            trunk_width: 16.,
+            length_ratio: (0.65, 0.82),
+            angle: (0.2, 0.6),
+            third_branch: 0.2,
+            taper: 0.7,
+            leaf_depth: 6,
+            leaves_per_cluster: 6,
+            leaf_size: 9.,
+            bark: srgba(62, 44, 35, 255),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Leaf {
+    // from the end of the branch, as if the branch pointed up
+    pub offset: Vec2,
+    pub angle: f32,
+    pub size: f32,
+    pub color: Srgba<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Branch {
+    // parents always come before their children
+    pub parent: Option<usize>,
+    pub depth: usize,
+    pub length: f32,
+    // turn from the parent's direction
+    pub angle: f32,
+    pub start_width: f32,
+    pub end_width: f32,
+    // so the branches don't all sway together
+    pub phase: f32,
+    pub leaves: Vec<Leaf>,
+}
+
+// How the wind bends the tree, the thin branches at the top move the most.
+#[derive(Debug, Clone, Copy)]
+pub struct Sway {
+    // radians at the tips
+    pub strength: f32,
+    pub speed: f32,
+    // a faster second wave on top of the slow one, 0 is a steady breeze
+    pub gust: f32,
+}
+
+impl Sway {
+    pub fn new(strength: f32, speed: f32, gust: f32) -> Self {
+        Self { strength, speed, gust }
+    }
+
+    pub fn calm() -> Self {
+        Sway::new(0., 1., 0.)
+    }
+
+    pub fn set_strength(&mut self, strength: f32) {
+        self.strength = strength;
+    }
+
+    // The turn for a branch depth deep in a tree max_depth deep, on top of its parent's.
+    // Leans away from the wind a little and sways around that. Every branch adds its share
+    // of the bend, the shares get bigger towards the top and add up to strength at the tips.
+    pub fn angle(&self, depth: usize, max_depth: usize, phase: f32, time: f32) -> f32 {
+        let bend = |d: usize| (d as f32 / max_depth.max(1) as f32).powi(2);
+        let share = bend(depth + 1) - bend(depth);
+        let t = time * self.speed;
+        let wave = ((t + phase).sin() + self.gust * (t * 2.7 + phase * 1.3).sin()) / (1. + self.gust);
+        -self.strength * share * (0.5 + wave * 0.5)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PosedBranch {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub start_width: f32,
+    pub end_width: f32,
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PosedLeaf {
+    pub position: Vec2,
+    pub angle: f32,
+    pub size: f32,
+    pub color: Srgba<u8>,
+}
+
+// Where everything is at one moment
+#[derive(Debug, Clone, Default)]
+pub struct Pose {
+    pub branches: Vec<PosedBranch>,
+    pub leaves: Vec<PosedLeaf>,
+}
+
+impl Pose {
+    // the branches as a turtle path, to save like the L-systems, the leaves are left out
+    pub fn segments(&self, color: Srgba<u8>) -> Vec<Segment> {
+        self.branches.iter().map(|b| Segment {
+            start: b.start,
+            end: b.end,
+            width: (b.start_width + b.end_width) / 2.,
+            color,
+            depth: b.depth,
+        }).collect()
+    }
+}
+
+pub struct Tree {
+    pub params: TreeParams,
+    pub branches: Vec<Branch>,
+}
+
+impl Tree {
+    pub fn new(params: TreeParams) -> Self {
+        let mut tree = Self { params, branches: vec![] };
+        tree.grow();
+        tree
+    }
+
+    // A new random tree with the same params
+    pub fn grow(&mut self) {
+        let p = &self.params;
+        let mut branches = vec![Branch {
+            parent: None,
+            depth: 0,
+            length: p.trunk_length,
+            // trunks are never quite straight
+            angle: random_range(-0.05, 0.05),
+            start_width: p.trunk_width,
+            end_width: p.trunk_width * p.taper,
+            phase: random_range(0., TAU),
+            leaves: vec![],
+        }];
+        // breadth first, so parents stay in front of their children
+        let mut ii = 0;
+        while ii < branches.len() {
+            let (depth, length, width) = (branches[ii].depth, branches[ii].length, branches[ii].end_width);
+            if depth + 1 < p.depth {
+                let count = if random::<f32>() < p.third_branch { 3 } else { 2 };
+                for c in 0..count {
+                    // one each side, a third one somewhere in the middle
+                    let side = match c {
+                        0 => 1.,
+                        1 => -1.,
+                        _ => random_range(-0.3, 0.3),
+                    };
+                    branches.push(Branch {
+                        parent: Some(ii),
+                        depth: depth + 1,
+                        length: length * random_range(p.length_ratio.0, p.length_ratio.1),
+                        angle: side * random_range(p.angle.0, p.angle.1),
+                        start_width: width,
+                        end_width: width * p.taper,
+                        phase: random_range(0., TAU),
+                        leaves: vec![],
+                    });
+                }
+            }
+            if depth >= p.leaf_depth {
+                branches[ii].leaves = (0..p.leaves_per_cluster).map(|_| Leaf {
+                    offset: vec2(random_range(-1., 1.), random_range(-0.5, 1.)) * p.leaf_size * 1.2,
+                    angle: random_range(0., TAU),
+                    size: p.leaf_size * random_range(0.6, 1.2),
+                    color: get_random_green(Some(220)),
+                }).collect();
+            }
+            ii += 1;
+        }
+        self.branches = branches;
+    }
+
+    // Everything in place at time, with the trunk at base growing up
+    pub fn pose(&self, base: Vec2, sway: &Sway, time: f32) -> Pose {
+        let max_depth = self.params.depth;
+        // the direction and end of every branch, for their children
+        let mut ends: Vec<(f32, Vec2)> = Vec::with_capacity(self.branches.len());
+        let mut pose = Pose::default();
+        for b in self.branches.iter() {
+            let (parent_angle, start) = match b.parent {
+                Some(parent) => ends[parent],
+                None => (PI / 2., base),
+            };
+            let angle = parent_angle + b.angle + sway.angle(b.depth, max_depth, b.phase, time);
+            let end = start + vec2(angle.cos(), angle.sin()) * b.length;
+            ends.push((angle, end));
+            pose.branches.push(PosedBranch {
+                start,
+                end,
+                start_width: b.start_width,
+                end_width: b.end_width,
+                depth: b.depth,
+            });
+            // leaves flutter faster than the branch they are on
+            let flutter = sway.strength * 0.5 * (time * sway.speed * 3. + b.phase).sin();
+            for leaf in b.leaves.iter() {
+                pose.leaves.push(PosedLeaf {
+                    position: end + Mat2::from_angle(angle - PI / 2.) * leaf.offset,
+                    angle: leaf.angle + angle + flutter,
+                    size: leaf.size,
+                    color: leaf.color,
+                });
+            }
+        }
+        pose
+    }
+
+    pub fn display(&self, draw: &Draw, base: Vec2, sway: &Sway, time: f32) {
+        let pose = self.pose(base, sway, time);
+        for b in pose.branches.iter() {
+            let n = (b.end - b.start).normalize_or_zero().perp();
+            draw.polygon()
+                .points(vec![
+                    b.start + n * b.start_width / 2.,
+                    b.end + n * b.end_width / 2.,
+                    b.end - n * b.end_width / 2.,
+                    b.start - n * b.start_width / 2.,
+                ])
+                .color(self.params.bark);
+            // covers the gap where a branch turns away from its parent
+            draw.ellipse().xy(b.end).radius(b.end_width / 2.).color(self.params.bark);
+        }
+        for l in pose.leaves.iter() {
+            draw.ellipse()
+                .xy(l.position)
+                .w_h(l.size, l.size * 0.5)
+                .rotate(l.angle)
+                .color(l.color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_and_tapers() {
+        let tree = Tree::new(TreeParams::default());
+        let p = &tree.params;
+        assert_eq!(tree.branches.iter().map(|b| b.depth).max(), Some(p.depth - 1));
+        for (ii, b) in tree.branches.iter().enumerate().skip(1) {
+            let parent = &tree.branches[b.parent.unwrap()];
+            assert!(b.parent.unwrap() < ii);
+            assert_eq!(b.depth, parent.depth + 1);
+            assert!(b.length < parent.length);
+            assert_eq!(b.start_width, parent.end_width);
+            assert_eq!(b.leaves.is_empty(), b.depth < p.leaf_depth);
+        }
+    }
+
+    #[test]
+    fn branches_stay_joined_in_the_wind() {
+        let tree = Tree::new(TreeParams::default());
+        let still = tree.pose(Vec2::ZERO, &Sway::calm(), 0.);
+        let windy = tree.pose(Vec2::ZERO, &Sway::new(0.3, 1., 0.5), 2.);
+        for pose in [&still, &windy] {
+            assert_eq!(pose.branches[0].start, Vec2::ZERO);
+            for (b, posed) in tree.branches.iter().zip(pose.branches.iter()).skip(1) {
+                assert!(posed.start.distance(pose.branches[b.parent.unwrap()].end) < 1e-3);
+            }
+        }
+        // the wind moves the tips more than the trunk
+        let moved = |ii: usize| still.branches[ii].end.distance(windy.branches[ii].end);
+        assert!(moved(tree.branches.len() - 1) > moved(0));
+    }
+
+    #[test]
+    fn tips_bend_by_the_strength() {
+        let mut tree = Tree::new(TreeParams::default());
+        // every branch at the top of its swing
+        for b in tree.branches.iter_mut() {
+            b.phase = PI / 2.;
+        }
+        let sway = Sway::new(0.3, 1., 0.);
+        let still = tree.pose(Vec2::ZERO, &Sway::calm(), 0.);
+        let windy = tree.pose(Vec2::ZERO, &sway, 0.);
+        assert_eq!(windy.segments(tree.params.bark).len(), tree.branches.len());
+        let angle = |b: &PosedBranch| (b.end - b.start).angle();
+        let tip = tree.branches.len() - 1;
+        assert_eq!(tree.branches[tip].depth, tree.params.depth - 1);
+        assert!((angle(&still.branches[tip]) - angle(&windy.branches[tip]) - sway.strength).abs() < 1e-4);
+        // the shares of all the depths add up to it
+        let total: f32 = (0..tree.params.depth).map(|d| sway.angle(d, tree.params.depth, PI / 2., 0.)).sum();
+        assert!((total + sway.strength).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,289 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use nannou::prelude::*;
+
+// A straight piece of the path, depth is how many pushes deep the turtle was
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub width: f32,
+    pub color: Srgba<u8>,
+    pub depth: usize,
+}
+
+// Everything push() saves and pop() brings back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TurtleState {
+    pub position: Vec2,
+    // radians, 0 is to the right
+    pub heading: f32,
+    pub pen_down: bool,
+    pub width: f32,
+    pub color: Srgba<u8>,
+}
+
+// Walks around leaving lines behind, in a path that can be drawn, or saved for a plotter
+#[derive(Debug, Clone)]
+pub struct Turtle {
+    pub state: TurtleState,
+    stack: Vec<TurtleState>,
+    path: Vec<Segment>,
+}
+
+impl Turtle {
+    // at the origin, facing up, drawing thin black lines
+    pub fn new() -> Self {
+        Turtle::at(Vec2::ZERO, PI / 2.)
+    }
+
+    pub fn at(position: Vec2, heading: f32) -> Self {
+        Self {
+            state: TurtleState { position, heading, pen_down: true, width: 1., color: srgba(0, 0, 0, 255) },
+            stack: vec![],
+            path: vec![],
+        }
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.state.position
+    }
+
+    pub fn heading(&self) -> f32 {
+        self.state.heading
+    }
+
+    pub fn direction(&self) -> Vec2 {
+        vec2(self.state.heading.cos(), self.state.heading.sin())
+    }
+
+    pub fn set_heading(&mut self, heading: f32) {
+        self.state.heading = heading;
+    }
+
+    pub fn set_width(&mut self, width: f32) {
+        self.state.width = width;
+    }
+
+    pub fn set_color(&mut self, color: Srgba<u8>) {
+        self.state.color = color;
+    }
+
+    pub fn pen_up(&mut self) {
+        self.state.pen_down = false;
+    }
+
+    pub fn pen_down(&mut self) {
+        self.state.pen_down = true;
+    }
+
+    // goes straight to p, with a line if the pen is down
+    pub fn move_to(&mut self, p: Vec2) {
+        if self.state.pen_down {
+            self.path.push(Segment {
+                start: self.state.position,
+                end: p,
+                width: self.state.width,
+                color: self.state.color,
+                depth: self.stack.len(),
+            });
+        }
+        self.state.position = p;
+    }
+
+    // moves without drawing whatever the pen is doing
+    pub fn jump_to(&mut self, p: Vec2) {
+        self.state.position = p;
+    }
+
+    pub fn forward(&mut self, distance: f32) {
+        self.move_to(self.state.position + self.direction() * distance);
+    }
+
+    pub fn back(&mut self, distance: f32) {
+        self.forward(-distance);
+    }
+
+    pub fn left(&mut self, angle: f32) {
+        self.state.heading += angle;
+    }
+
+    pub fn right(&mut self, angle: f32) {
+        self.state.heading -= angle;
+    }
+
+    pub fn push(&mut self) {
+        self.stack.push(self.state);
+    }
+
+    // popping an empty stack leaves the turtle where it is
+    pub fn pop(&mut self) {
+        if let Some(state) = self.stack.pop() {
+            self.state = state;
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn path(&self) -> &[Segment] {
+        &self.path
+    }
+
+    pub fn take_path(&mut self) -> Vec<Segment> {
+        std::mem::take(&mut self.path)
+    }
+
+    pub fn clear(&mut self) {
+        self.path.clear();
+    }
+}
+
+impl Default for Turtle {
+    fn default() -> Self {
+        Turtle::new()
+    }
+}
+
+pub fn bounds(segments: &[Segment]) -> Rect {
+    let first = match segments.first() {
+        Some(s) => s.start,
+        None => return Rect::from_w_h(0., 0.),
+    };
+    let (min, max) = segments.iter().fold((first, first), |(min, max), s| {
+        (min.min(s.start).min(s.end), max.max(s.start).max(s.end))
+    });
+    Rect::from_corners(min, max)
+}
+
+// Scales and moves the segments to fill rect, keeping their proportions
+pub fn fit(segments: &[Segment], rect: Rect) -> Vec<Segment> {
+    let b = bounds(segments);
+    let scale = (rect.w() / b.w().max(1e-6)).min(rect.h() / b.h().max(1e-6));
+    let to = |p: Vec2| rect.xy() + (p - b.xy()) * scale;
+    segments.iter().map(|s| Segment { start: to(s.start), end: to(s.end), ..*s }).collect()
+}
+
+pub fn draw_path(draw: &Draw, segments: &[Segment]) {
+    for s in segments.iter() {
+        draw.line()
+            .start(s.start)
+            .end(s.end)
+            .weight(s.width)
+            .caps_round()
+            .color(s.color);
+    }
+}
+
+// Joins segments that carry on from the one before with the same pen into polylines,
+// so a plotter doesn't lift the pen for every one of them
+fn chains(segments: &[Segment]) -> Vec<(Vec<Vec2>, f32, Srgba<u8>)> {
+    let mut chains: Vec<(Vec<Vec2>, f32, Srgba<u8>)> = vec![];
+    for s in segments.iter() {
+        match chains.last_mut() {
+            Some((points, width, color))
+                if *width == s.width && *color == s.color && points.last().is_some_and(|p| p.distance(s.start) < 1e-4) => {
+                points.push(s.end);
+            }
+            _ => chains.push((vec![s.start, s.end], s.width, s.color)),
+        }
+    }
+    chains
+}
+
+// An svg the size of the path plus margin, with y flipped since svg has it going down
+pub fn to_svg(segments: &[Segment], margin: f32) -> String {
+    let b = bounds(segments).pad(-margin);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.2}\" height=\"{h:.2}\" viewBox=\"0 0 {w:.2} {h:.2}\">\n",
+        w = b.w(), h = b.h(),
+    );
+    for (points, width, color) in chains(segments) {
+        let points: Vec<String> = points.iter()
+            .map(|p| format!("{:.2},{:.2}", p.x - b.left(), b.top() - p.y))
+            .collect();
+        svg.push_str(&format!(
+            "  <polyline points=\"{}\" fill=\"none\" stroke=\"rgb({},{},{})\" stroke-opacity=\"{:.3}\" stroke-width=\"{:.2}\" stroke-linecap=\"round\"/>\n",
+            points.join(" "), color.red, color.green, color.blue, color.alpha as f32 / 255., width,
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub fn save_svg<P: AsRef<Path>>(segments: &[Segment], margin: f32, path: P) -> io::Result<()> {
+    fs::write(path, to_svg(segments, margin))
+}
+
+// HPGL for pen plotters, scale turns our units into plotter units (40 of them to a millimetre).
+// Plotters have y going up like we do, the path is only moved so it starts at 0,0.
+// There is one pen, widths and colors are up to whoever puts it in.
+pub fn to_hpgl(segments: &[Segment], scale: f32) -> String {
+    let b = bounds(segments);
+    let to = |p: Vec2| {
+        let p = (p - vec2(b.left(), b.bottom())) * scale;
+        format!("{},{}", p.x.round() as i64, p.y.round() as i64)
+    };
+    let mut hpgl = String::from("IN;SP1;\n");
+    for (points, _, _) in chains(segments) {
+        let rest: Vec<String> = points[1..].iter().map(|p| to(*p)).collect();
+        hpgl.push_str(&format!("PU{};PD{};\n", to(points[0]), rest.join(",")));
+    }
+    hpgl.push_str("PU;SP0;\n");
+    hpgl
+}
+
+pub fn save_hpgl<P: AsRef<Path>>(segments: &[Segment], scale: f32, path: P) -> io::Result<()> {
+    fs::write(path, to_hpgl(segments, scale))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walks_and_remembers() {
+        let mut turtle = Turtle::new();
+        turtle.forward(10.);
+        turtle.push();
+        turtle.right(PI / 2.);
+        turtle.set_width(3.);
+        turtle.forward(5.);
+        assert_eq!(turtle.depth(), 1);
+        turtle.pop();
+        assert_eq!(turtle.state.width, 1.);
+        turtle.pen_up();
+        turtle.forward(10.);
+        turtle.pen_down();
+        turtle.left(PI / 2.);
+        turtle.forward(1.);
+
+        let path = turtle.path();
+        assert_eq!(path.len(), 3);
+        assert!(path[1].end.distance(vec2(5., 10.)) < 1e-4);
+        assert_eq!((path[1].width, path[1].depth), (3., 1));
+        assert!(path[2].start.distance(vec2(0., 20.)) < 1e-4);
+        assert!(path[2].end.distance(vec2(-1., 20.)) < 1e-4);
+    }
+
+    #[test]
+    fn exports() {
+        let mut turtle = Turtle::new();
+        turtle.forward(10.);
+        turtle.right(PI / 2.);
+        turtle.forward(10.);
+        turtle.jump_to(vec2(0., 0.));
+        turtle.forward(5.);
+        let path = turtle.take_path();
+
+        let svg = to_svg(&path, 1.);
+        assert!(svg.contains("viewBox=\"0 0 12.00 12.00\""));
+        // the first two are joined, y flipped
+        assert!(svg.contains("points=\"1.00,11.00 1.00,1.00 11.00,1.00\""));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+
+        assert_eq!(to_hpgl(&path, 2.), "IN;SP1;\nPU0,0;PD0,20,20,20;\nPU0,0;PD10,0;\nPU;SP0;\n");
+    }
+}
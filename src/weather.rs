@@ -0,0 +1,459 @@
+use nannou::glam::Mat2;
+use nannou::noise::NoiseFn;
+use nannou::prelude::*;
+use crate::collision::Response;
+use crate::particle::Particle;
+use crate::noise_source::{NoiseKind, NoiseSource};
+use crate::get_random_retro;
+
+// The few knobs that drive the whole weather layer
+#[derive(Debug, Clone)]
+pub struct WeatherParams {
+    // steady horizontal wind, in pixels per step
+    pub wind: f32,
+    // how strong the gusts get on top of the steady wind
+    pub gusts: f32,
+    // splash droplets spawned for every drop that hits something
+    pub splash: usize,
+    // how much every drop adds to the puddle it lands in
+    pub accumulation: f32,
+    // how much a puddle loses per step
+    pub evaporation: f32,
+}
+
+impl Default for WeatherParams {
+    fn default() -> Self {
+        Self {
+            wind: 1.,
+            gusts: 3.,
+            splash: 3,
+            accumulation: 0.05,
+            evaporation: 0.002,
+        }
+    }
+}
+
+// Noise driven gusts, the wind changes slowly over time and a little with height
+pub struct Wind {
+    noise: NoiseSource,
+}
+
+impl Wind {
+    pub fn new() -> Self {
+        let mut noise = NoiseSource::new(NoiseKind::Perlin);
+        noise.set_seed(random());
+        Self { noise }
+    }
+
+    pub fn at(&self, params: &WeatherParams, time: f32, position: Vec2) -> Vec2 {
+        let gust = self.noise.get([time as f64 * 0.3, position.y as f64 * 0.002]) as f32;
+        vec2(params.wind + gust * params.gusts, 0.)
+    }
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Wind::new()
+    }
+}
+
+// What piles up on a flat surface, a row of little columns that spread and evaporate
+pub struct Accumulation {
+    pub left: f32,
+    pub top: f32,
+    pub cell_width: f32,
+    pub max_height: f32,
+    heights: Vec<f32>,
+}
+
+impl Accumulation {
+    pub fn new(left: f32, right: f32, top: f32, cell_width: f32, max_height: f32) -> Self {
+        let cells = ((right - left) / cell_width).ceil().max(1.) as usize;
+        Self {
+            left,
+            top,
+            cell_width,
+            max_height,
+            heights: vec![0.; cells],
+        }
+    }
+
+    pub fn from_rect(rect: Rect, cell_width: f32, max_height: f32) -> Self {
+        Accumulation::new(rect.left(), rect.right(), rect.top(), cell_width, max_height)
+    }
+
+    pub fn add(&mut self, x: f32, amount: f32) {
+        let ii = ((x - self.left) / self.cell_width).floor();
+        if ii >= 0. && (ii as usize) < self.heights.len() {
+            let h = &mut self.heights[ii as usize];
+            *h = (*h + amount).min(self.max_height);
+        }
+    }
+
+    pub fn right(&self) -> f32 {
+        self.left + self.heights.len() as f32 * self.cell_width
+    }
+
+    pub fn height_at(&self, x: f32) -> f32 {
+        let ii = ((x - self.left) / self.cell_width).floor();
+        if ii >= 0. && (ii as usize) < self.heights.len() {
+            self.heights[ii as usize]
+        } else {
+            0.
+        }
+    }
+
+    // spread sideways like a liquid (or a pile), and evaporate a bit
+    pub fn update(&mut self, spread: f32, evaporation: f32) {
+        let before = self.heights.clone();
+        let last = before.len() - 1;
+        for (ii, h) in self.heights.iter_mut().enumerate() {
+            let left = before[ii.saturating_sub(1)];
+            let right = before[(ii + 1).min(last)];
+            *h += ((left + right) * 0.5 - before[ii]) * spread;
+            *h = (*h - evaporation).max(0.);
+        }
+    }
+
+    pub fn display(&self, draw: &Draw, color: Srgba<u8>) {
+        for (ii, h) in self.heights.iter().enumerate() {
+            if *h < 0.2 {
+                continue;
+            }
+            let x = self.left + (ii as f32 + 0.5) * self.cell_width;
+            draw.rect()
+                .x_y(x, self.top + h * 0.5)
+                .w_h(self.cell_width, *h)
+                .color(color);
+        }
+    }
+}
+
+// Droplets thrown up when a drop hits something, they live for a few steps under gravity
+pub struct Splashes {
+    pub gravity: f32,
+    drops: Vec<(Particle, u32)>,
+}
+
+const SPLASH_LIFE: u32 = 30;
+
+fn splash_draw_fn(draw: &Draw, location: Vec2, color: Srgba<u8>) {
+    draw.ellipse().xy(location).radius(1.).color(color);
+}
+
+impl Splashes {
+    pub fn new(gravity: f32) -> Self {
+        Self { gravity, drops: vec![] }
+    }
+
+    // speed is the impact speed, faster drops throw the droplets further
+    pub fn spawn(&mut self, position: Vec2, speed: f32, wind: Vec2, color: Srgba<u8>, count: usize) {
+        for _ in 0..count {
+            let v = vec2(random_range(-1., 1.), random_range(0.5, 1.)) * speed * 0.3 + wind * 0.3;
+            self.drops.push((Particle::new(position, color, v, splash_draw_fn), 0));
+        }
+    }
+
+    pub fn update(&mut self) {
+        let gravity = vec2(0., -self.gravity);
+        for (drop, age) in self.drops.iter_mut() {
+            drop.apply_force(gravity);
+            drop.update();
+            *age += 1;
+            let mut c = drop.color();
+            c.alpha = map_range(*age, 0, SPLASH_LIFE, 200, 0);
+            drop.set_color(c);
+        }
+        self.drops.retain(|(_, age)| *age < SPLASH_LIFE);
+    }
+
+    pub fn display(&self, draw: &Draw) {
+        for (drop, _) in self.drops.iter() {
+            drop.display(draw);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.drops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.drops.is_empty()
+    }
+}
+
+// What falls from the sky. Every kind falls, drifts and lands in its own way,
+// the particles stay plain Particles so they can share collisions with everything else.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Precipitation {
+    Rain,
+    Snow,
+    Hail,
+    Sleet,
+}
+
+// Particles want a draw function, the shapes that need more than the location are in display()
+fn precipitation_draw_fn(draw: &Draw, location: Vec2, color: Srgba<u8>) {
+    draw.ellipse().xy(location).radius(1.5).color(color);
+}
+
+impl Precipitation {
+    pub fn next(self) -> Self {
+        match self {
+            Precipitation::Rain => Precipitation::Snow,
+            Precipitation::Snow => Precipitation::Hail,
+            Precipitation::Hail => Precipitation::Sleet,
+            Precipitation::Sleet => Precipitation::Rain,
+        }
+    }
+
+    pub fn spawn(self, position: Vec2) -> Particle {
+        let (v, c) = match self {
+            Precipitation::Rain => (random_range(-9., -4.), get_random_retro(Some(200))),
+            Precipitation::Snow => (random_range(-2., -0.8), srgba(240, 245, 255, 220)),
+            Precipitation::Hail => (random_range(-10., -6.), srgba(220, 235, 240, 230)),
+            Precipitation::Sleet => (random_range(-8., -5.), srgba(203, 241, 245, 200)),
+        };
+        Particle::new(position, c, vec2(0., v), precipitation_draw_fn)
+    }
+
+    // how much the wind pushes it around, light flakes drift more than the hail
+    pub fn wind_factor(self) -> f32 {
+        match self {
+            Precipitation::Rain => 1.,
+            Precipitation::Snow => 1.5,
+            Precipitation::Hail => 0.3,
+            Precipitation::Sleet => 0.8,
+        }
+    }
+
+    // only hail is heavy enough to speed up after a bounce, the rest fall at their own speed
+    pub fn gravity(self) -> f32 {
+        match self {
+            Precipitation::Hail => 0.25,
+            _ => 0.,
+        }
+    }
+
+    pub fn terminal_speed(self) -> f32 {
+        match self {
+            Precipitation::Hail => 12.,
+            _ => 10.,
+        }
+    }
+
+    pub fn response(self) -> Response {
+        match self {
+            Precipitation::Hail => Response::Bounce { restitution: 0.4 },
+            _ => Response::Absorb,
+        }
+    }
+
+    // how many splash droplets and how much accumulation, relative to rain
+    pub fn splash(self) -> f32 {
+        match self {
+            Precipitation::Rain => 1.,
+            Precipitation::Snow => 0.,
+            Precipitation::Hail => 0.5,
+            Precipitation::Sleet => 0.5,
+        }
+    }
+
+    pub fn accumulation(self) -> f32 {
+        match self {
+            Precipitation::Rain => 1.,
+            Precipitation::Snow => 4.,
+            Precipitation::Hail => 0.5,
+            Precipitation::Sleet => 2.,
+        }
+    }
+
+    // puddles for rain, caps of snow for the rest
+    pub fn surface_color(self) -> Srgba<u8> {
+        match self {
+            Precipitation::Rain => srgba(113, 201, 206, 120),
+            _ => srgba(235, 240, 250, 200),
+        }
+    }
+
+    // Moves a particle one step, phase is any number that is different for every particle
+    pub fn step(self, particle: &mut Particle, wind: Vec2, time: f32, phase: f32) {
+        let mut v = particle.velocity();
+        v.x += (wind.x * self.wind_factor() - v.x) * 0.05;
+        v.y = (v.y - self.gravity()).max(-self.terminal_speed());
+        particle.set_velocity(v);
+        particle.update();
+        if self == Precipitation::Snow {
+            // flakes wobble from side to side as they fall
+            particle.set_location(particle.location() + vec2((time * 2. + phase).sin() * 0.6, 0.));
+        }
+    }
+
+    pub fn display(self, draw: &Draw, particle: &Particle, time: f32, phase: f32) {
+        let location = particle.location();
+        let heading = particle.velocity().normalize_or_zero();
+        match self {
+            // a streak along the direction it falls, so it slants with the wind
+            Precipitation::Rain => {
+                draw.line()
+                    .start(location)
+                    .end(location - heading * 10.)
+                    .color(get_random_retro(Some(100)));
+            }
+            Precipitation::Snow => {
+                let angle = time * 1.5 + phase;
+                for ii in 0..3 {
+                    let arm = Mat2::from_angle(angle + ii as f32 * PI / 3.) * vec2(3., 0.);
+                    draw.line()
+                        .start(location - arm)
+                        .end(location + arm)
+                        .weight(1.)
+                        .color(particle.color());
+                }
+            }
+            Precipitation::Hail => {
+                draw.ellipse().xy(location).radius(2.).color(particle.color());
+            }
+            Precipitation::Sleet => {
+                draw.line()
+                    .start(location)
+                    .end(location - heading * 5.)
+                    .color(particle.color());
+                draw.ellipse().xy(location).radius(1.).color(particle.color());
+            }
+        }
+    }
+}
+
+// Wind, splashes and the puddles on every surface, driven by WeatherParams
+pub struct Weather {
+    pub params: WeatherParams,
+    pub precipitation: Precipitation,
+    pub wind: Wind,
+    pub splashes: Splashes,
+    pub surfaces: Vec<Accumulation>,
+}
+
+impl Weather {
+    pub fn new(params: WeatherParams) -> Self {
+        Self {
+            params,
+            precipitation: Precipitation::Rain,
+            wind: Wind::new(),
+            splashes: Splashes::new(0.2),
+            surfaces: vec![],
+        }
+    }
+
+    // one puddle along the top of every rect, pass only the tops nothing covers
+    pub fn set_surfaces(&mut self, rects: &[Rect]) {
+        self.surfaces = rects.iter().map(|r| Accumulation::from_rect(*r, 4., 12.)).collect();
+    }
+
+    pub fn wind_at(&self, time: f32, position: Vec2) -> Vec2 {
+        self.wind.at(&self.params, time, position)
+    }
+
+    // A drop hit something, it settles on the surface it landed on, if there is one there
+    pub fn impact(&mut self, time: f32, position: Vec2, velocity: Vec2, color: Srgba<u8>) {
+        let wind = self.wind_at(time, position);
+        let count = (self.params.splash as f32 * self.precipitation.splash()).round() as usize;
+        self.splashes.spawn(position, velocity.length(), wind, color, count);
+        let surface = self.surfaces.iter_mut()
+            .find(|s| position.x >= s.left && position.x < s.right() && (position.y - s.top).abs() < 1.);
+        if let Some(s) = surface {
+            s.add(position.x, self.params.accumulation * self.precipitation.accumulation());
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.splashes.update();
+        for s in self.surfaces.iter_mut() {
+            s.update(0.2, self.params.evaporation);
+        }
+    }
+
+    pub fn display(&self, draw: &Draw) {
+        for s in self.surfaces.iter() {
+            s.display(draw, self.precipitation.surface_color());
+        }
+        self.splashes.display(draw);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::{circle_rect, resolve};
+
+    #[test]
+    fn accumulation_fills_cells() {
+        let mut a = Accumulation::new(0., 40., 10., 4., 3.);
+        assert_eq!(a.right(), 40.);
+        a.add(5., 1.);
+        assert_eq!(a.height_at(4.), 1.);
+        assert_eq!(a.height_at(7.9), 1.);
+        assert_eq!(a.height_at(8.), 0.);
+        // capped at max_height, and nothing lands outside
+        a.add(5., 10.);
+        assert_eq!(a.height_at(5.), 3.);
+        a.add(-1., 1.);
+        a.add(40., 1.);
+        assert_eq!(a.height_at(-1.), 0.);
+        assert_eq!(a.height_at(40.), 0.);
+    }
+
+    #[test]
+    fn accumulation_spreads_and_evaporates() {
+        let mut a = Accumulation::new(0., 12., 0., 4., 10.);
+        a.add(5., 2.);
+        a.update(0.5, 0.);
+        assert_eq!(a.height_at(1.), 0.5);
+        assert_eq!(a.height_at(5.), 1.);
+        assert_eq!(a.height_at(9.), 0.5);
+        // evaporation never goes below zero
+        a.update(0., 0.75);
+        assert_eq!(a.height_at(1.), 0.);
+        assert_eq!(a.height_at(5.), 0.25);
+    }
+
+    #[test]
+    fn precipitation_kinds() {
+        let mut kind = Precipitation::Rain;
+        let mut seen = vec![];
+        for _ in 0..4 {
+            seen.push(kind);
+            kind = kind.next();
+        }
+        assert_eq!(kind, Precipitation::Rain);
+        assert_eq!(seen, vec![Precipitation::Rain, Precipitation::Snow, Precipitation::Hail, Precipitation::Sleet]);
+
+        // hail bounces back up off a roof, rain soaks in
+        let roof = Rect::from_x_y_w_h(0., -10., 100., 20.);
+        let mut hail = Precipitation::Hail.spawn(vec2(0., 3.));
+        let contact = circle_rect(hail.location(), 5., roof).unwrap();
+        assert!(resolve(&mut hail, &contact, Precipitation::Hail.response()));
+        assert!(hail.velocity().y > 0.);
+        let mut rain = Precipitation::Rain.spawn(vec2(0., 3.));
+        assert!(!resolve(&mut rain, &contact, Precipitation::Rain.response()));
+    }
+
+    #[test]
+    fn snow_drifts_more_than_rain() {
+        let sideways = |kind: Precipitation, wind: Vec2| {
+            let mut p = kind.spawn(Vec2::ZERO);
+            let mut xs = vec![];
+            for ii in 0..200 {
+                kind.step(&mut p, wind, ii as f32 * 0.05, 0.);
+                xs.push(p.location().x);
+            }
+            xs
+        };
+        let wind = vec2(2., 0.);
+        let (snow, rain) = (sideways(Precipitation::Snow, wind), sideways(Precipitation::Rain, wind));
+        assert!(snow.last().unwrap() > rain.last().unwrap());
+        // without wind the rain falls straight, the flakes still wobble
+        assert!(sideways(Precipitation::Rain, Vec2::ZERO).iter().all(|x| *x == 0.));
+        assert!(sideways(Precipitation::Snow, Vec2::ZERO).iter().any(|x| x.abs() > 1.));
+    }
+}